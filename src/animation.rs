@@ -0,0 +1,271 @@
+use crossterm::{cursor, execute};
+use image::codecs::gif::GifDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, Frame, Frames, ImageFormat};
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+use std::thread;
+use std::time::{Duration, Instant};
+use termcolor::{Color, ColorSpec, WriteColor};
+
+use crate::config::get_config;
+use crate::graphics::print_image;
+use crate::render::get_stdout;
+use crate::utils::read_head;
+
+/// Browsers play frames with a delay this short at 100ms instead.
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// How often playback checks for Ctrl-C while waiting for the next frame.
+const INTERRUPT_POLL: Duration = Duration::from_millis(10);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static PLAYING: AtomicBool = AtomicBool::new(false);
+static INTERRUPT_HANDLER: Once = Once::new();
+
+/// Handles `--frame` and `--animate` for animated GIFs and WebPs. Returns
+/// false for still images, or when neither option is set, so the caller
+/// prints the image as usual.
+pub fn render_animated_image(path: &Path, viuer_config: &viuer::Config) -> io::Result<bool> {
+    let config = get_config();
+    let animate =
+        config.animate || config.animation_loops.is_some() || config.animation_seconds.is_some();
+    if config.frame.is_none() && !animate {
+        return Ok(false);
+    }
+    let Some(frames) = animation_frames(path)? else {
+        return Ok(false);
+    };
+
+    if let Some(number) = config.frame {
+        // Only the frames up to the requested one are decoded
+        let mut frames = frames;
+        let mut count = 0;
+        let frame = match number.checked_sub(1) {
+            Some(index) => frames.by_ref().inspect(|_| count += 1).nth(index),
+            None => None,
+        };
+        let Some(frame) = frame.transpose().map_err(io::Error::other)? else {
+            // Frames that were not reached still count towards the total
+            count += frames.count();
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Frame {} is out of range, the image has {} frames",
+                    number, count
+                ),
+            ));
+        };
+        let temp_dir = tempfile::TempDir::new()?;
+        let frame_path = write_frame(&frame, temp_dir.path(), 0)?;
+        print_image(&frame_path, viuer_config)?;
+        return Ok(true);
+    }
+
+    let frames = frames.collect_frames().map_err(io::Error::other)?;
+    if frames.len() < 2 {
+        return Ok(false);
+    }
+    play(&frames, viuer_config)?;
+    Ok(true)
+}
+
+/// Prints the frame count and the length of one loop of an animated image.
+pub fn print_animation_summary(path: &Path) -> io::Result<()> {
+    let Ok(Some((count, duration))) = animation_length(path) else {
+        return Ok(());
+    };
+    if count < 2 {
+        return Ok(());
+    }
+
+    let mut stdout = get_stdout();
+    stdout.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)).set_dimmed(true))?;
+    write!(
+        stdout,
+        "{} frames, {:.2}s per loop",
+        count,
+        duration.as_secs_f64()
+    )?;
+    stdout.reset()?;
+    writeln!(stdout)
+}
+
+/// The frame count and loop length of a GIF or animated WebP, read from the
+/// frame headers without decoding any frame. Returns `None` for any other
+/// image.
+fn animation_length(path: &Path) -> io::Result<Option<(usize, Duration)>> {
+    let head = read_head(path)?;
+    let reader = BufReader::new(File::open(path)?);
+
+    if head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") {
+        let mut options = gif::DecodeOptions::new();
+        options.skip_frame_decoding(true);
+        let mut decoder = options.read_info(reader).map_err(io::Error::other)?;
+        let mut count = 0;
+        let mut duration = Duration::ZERO;
+        while let Some(frame) = decoder.next_frame_info().map_err(io::Error::other)? {
+            count += 1;
+            // GIF delays are in hundredths of a second
+            duration += clamp_delay(Duration::from_millis(u64::from(frame.delay) * 10));
+        }
+        Ok(Some((count, duration)))
+    } else if head.starts_with(b"RIFF") && head.get(8..12) == Some(b"WEBP") {
+        let decoder = image_webp::WebPDecoder::new(reader).map_err(io::Error::other)?;
+        if !decoder.is_animated() {
+            return Ok(None);
+        }
+        Ok(Some((
+            decoder.num_frames() as usize,
+            Duration::from_millis(decoder.loop_duration()),
+        )))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The frames of a GIF or animated WebP, decoded one by one as they are
+/// read.
+fn animation_frames(path: &Path) -> io::Result<Option<Frames<'static>>> {
+    let head = read_head(path)?;
+    let reader = BufReader::new(File::open(path)?);
+
+    if head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") {
+        let decoder = GifDecoder::new(reader).map_err(io::Error::other)?;
+        Ok(Some(decoder.into_frames()))
+    } else if head.starts_with(b"RIFF") && head.get(8..12) == Some(b"WEBP") {
+        let decoder = WebPDecoder::new(reader).map_err(io::Error::other)?;
+        if !decoder.has_animation() {
+            return Ok(None);
+        }
+        Ok(Some(decoder.into_frames()))
+    } else {
+        Ok(None)
+    }
+}
+
+fn frame_delay(frame: &Frame) -> Duration {
+    let (numerator, denominator) = frame.delay().numer_denom_ms();
+    clamp_delay(Duration::from_millis(u64::from(
+        numerator / denominator.max(1),
+    )))
+}
+
+fn clamp_delay(delay: Duration) -> Duration {
+    if delay < MIN_FRAME_DELAY {
+        DEFAULT_FRAME_DELAY
+    } else {
+        delay
+    }
+}
+
+/// Frames go through PNG files because viuer links a different version of
+/// the `image` crate.
+fn write_frame(frame: &Frame, dir: &Path, index: usize) -> io::Result<PathBuf> {
+    let path = dir.join(format!("frame-{:05}.png", index));
+    frame
+        .buffer()
+        .save_with_format(&path, ImageFormat::Png)
+        .map_err(io::Error::other)?;
+    Ok(path)
+}
+
+/// Plays the animation in place for `--loops` loops or `--animation-seconds`
+/// seconds, whichever ends first. Ctrl-C stops playback with an
+/// `Interrupted` error, once the frames' temporary files are removed.
+fn play(frames: &[Frame], viuer_config: &viuer::Config) -> io::Result<()> {
+    let config = get_config();
+    let deadline = config
+        .animation_seconds
+        .map(|seconds| Instant::now() + Duration::from_secs(seconds));
+    let loops = match (config.animation_loops, deadline) {
+        (Some(loops), _) => loops,
+        (None, Some(_)) => u32::MAX,
+        (None, None) => 1,
+    };
+
+    INTERRUPT_HANDLER.call_once(|| {
+        // Without a handler Ctrl-C still ends the program, just less tidily.
+        // The handler stays installed, so outside playback it exits the way
+        // Ctrl-C would without one. Playback covers the whole life of the
+        // frames' temporary files, so those are never left behind.
+        let _ = ctrlc::set_handler(|| {
+            if PLAYING.load(Ordering::Relaxed) {
+                INTERRUPTED.store(true, Ordering::Relaxed);
+            } else {
+                std::process::exit(130);
+            }
+        });
+    });
+
+    PLAYING.store(true, Ordering::Relaxed);
+    let result = play_from_files(frames, loops, deadline, viuer_config);
+    PLAYING.store(false, Ordering::Relaxed);
+
+    if INTERRUPTED.load(Ordering::Relaxed) {
+        return Err(io::Error::new(
+            io::ErrorKind::Interrupted,
+            "Animation interrupted",
+        ));
+    }
+    result
+}
+
+fn play_from_files(
+    frames: &[Frame],
+    loops: u32,
+    deadline: Option<Instant>,
+    viuer_config: &viuer::Config,
+) -> io::Result<()> {
+    // Encode every frame up front so later loops only have to print them
+    let temp_dir = tempfile::TempDir::new()?;
+    let paths = frames
+        .iter()
+        .enumerate()
+        .map(|(index, frame)| write_frame(frame, temp_dir.path(), index))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let mut stdout = io::stdout();
+    execute!(stdout, cursor::Hide)?;
+    let result = play_frames(frames, &paths, loops, deadline, viuer_config);
+    execute!(stdout, cursor::Show)?;
+    result
+}
+
+fn play_frames(
+    frames: &[Frame],
+    paths: &[PathBuf],
+    loops: u32,
+    deadline: Option<Instant>,
+    viuer_config: &viuer::Config,
+) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let mut printed_height = None;
+
+    for _ in 0..loops.max(1) {
+        for (frame, path) in frames.iter().zip(paths) {
+            // Draw over the previous frame
+            if let Some(height) = printed_height {
+                execute!(stdout, cursor::MoveToPreviousLine(height))?;
+            }
+            let (_, height) = print_image(path, viuer_config)?;
+            printed_height = Some(height as u16);
+
+            let next_frame = Instant::now() + frame_delay(frame);
+            while Instant::now() < next_frame {
+                if INTERRUPTED.load(Ordering::Relaxed)
+                    || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+                {
+                    return Ok(());
+                }
+                thread::sleep(INTERRUPT_POLL);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,12 +1,13 @@
 use serde_json::Value;
 use std::io::{self, Read};
+use std::path::Path;
 use std::sync::atomic::Ordering;
 use std::sync::OnceLock;
 use tempfile::TempDir;
 
 use crate::config::AppConfig;
 use crate::constants::{DEBUG_MODE, IMAGE_FOLDER, NO_IMAGES};
-use crate::utils::ast;
+use crate::utils::{ast, decode_text, read_text_file};
 
 pub static APP_STATE: OnceLock<AppState> = OnceLock::new();
 
@@ -31,11 +32,11 @@ impl AppState {
 
 pub fn read_content(file_path: Option<String>) -> io::Result<String> {
     match file_path {
-        Some(path) => std::fs::read_to_string(path),
+        Some(path) => read_text_file(Path::new(&path)),
         None => {
-            let mut buffer = String::new();
-            io::stdin().read_to_string(&mut buffer)?;
-            Ok(buffer)
+            let mut buffer = Vec::new();
+            io::stdin().read_to_end(&mut buffer)?;
+            Ok(decode_text(&buffer).0)
         }
     }
 }
@@ -47,10 +48,16 @@ pub fn parse_and_process_markdown(content: &str) -> io::Result<Value> {
     let mut json: Value = serde_json::from_str(&serde_json::to_string(&ast).unwrap())
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
 
-    ast::process_definitions(&json);
-    ast::process_footnotes(&json);
-    ast::modify_heading_ast(&mut json);
-    ast::modify_list_item_ast(&mut json);
+    process_markdown_ast(&mut json);
 
     Ok(json)
 }
+
+/// Prepares an mdast tree for `render_markdown`, whether it came from the
+/// Markdown parser or from one of the other document converters.
+pub fn process_markdown_ast(json: &mut Value) {
+    ast::process_definitions(json);
+    ast::process_footnotes(json);
+    ast::modify_heading_ast(json);
+    ast::modify_list_item_ast(json);
+}
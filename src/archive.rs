@@ -0,0 +1,287 @@
+use bzip2::read::BzDecoder;
+use devicons::{icon_for_file, File, Theme};
+use flate2::read::GzDecoder;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use termcolor::{Color, ColorSpec, WriteColor};
+use time::OffsetDateTime;
+use xz2::read::XzDecoder;
+
+use crate::directory_tree::{
+    DIRECTORY_ICON, TREE_BRANCH, TREE_INDENT, TREE_LAST_BRANCH, TREE_VERTICAL,
+};
+use crate::render::get_stdout;
+use crate::utils::{format_size, read_head};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+    TarBz2,
+    TarXz,
+    TarZst,
+}
+
+impl ArchiveKind {
+    fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") || name.ends_with(".crate") {
+            return Some(ArchiveKind::TarGz);
+        }
+        if name.ends_with(".tar.bz2") || name.ends_with(".tbz") || name.ends_with(".tbz2") {
+            return Some(ArchiveKind::TarBz2);
+        }
+        if name.ends_with(".tar.xz") || name.ends_with(".txz") {
+            return Some(ArchiveKind::TarXz);
+        }
+        if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            return Some(ArchiveKind::TarZst);
+        }
+        if name.ends_with(".tar") {
+            return Some(ArchiveKind::Tar);
+        }
+        if name.ends_with(".zip") || name.ends_with(".jar") || name.ends_with(".whl") {
+            return Some(ArchiveKind::Zip);
+        }
+
+        let head = read_head(path).ok()?;
+        if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+            Some(ArchiveKind::Zip)
+        } else if head.get(257..262) == Some(b"ustar") {
+            Some(ArchiveKind::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+struct ArchiveEntry {
+    path: String,
+    size: u64,
+    modified: Option<String>,
+    is_dir: bool,
+}
+
+#[derive(Default)]
+struct TreeNode {
+    entry: Option<ArchiveEntry>,
+    children: BTreeMap<String, TreeNode>,
+}
+
+impl TreeNode {
+    fn insert(&mut self, entry: ArchiveEntry) {
+        let components: Vec<String> = entry
+            .path
+            .split('/')
+            .filter(|component| !component.is_empty() && *component != ".")
+            .map(str::to_string)
+            .collect();
+        let mut node = self;
+        for component in components {
+            node = node.children.entry(component).or_default();
+        }
+        node.entry = Some(entry);
+    }
+
+    fn is_dir(&self) -> bool {
+        !self.children.is_empty() || self.entry.as_ref().is_some_and(|entry| entry.is_dir)
+    }
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    ArchiveKind::detect(path).is_some()
+}
+
+/// Splits `archive.zip:path/inside/file` into the archive and member path.
+pub fn split_member_path(path: &Path) -> Option<(PathBuf, String)> {
+    let path = path.to_str()?;
+    let (archive, member) = path.rsplit_once(':')?;
+    let archive = PathBuf::from(archive);
+    if member.is_empty() || !archive.is_file() || !is_archive(&archive) {
+        return None;
+    }
+    Some((archive, member.trim_start_matches('/').to_string()))
+}
+
+pub fn render_archive(path: &Path) -> io::Result<()> {
+    let kind = ArchiveKind::detect(path)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Unsupported archive"))?;
+    let mut root = TreeNode::default();
+    for entry in list_entries(path, kind)? {
+        root.insert(entry);
+    }
+
+    let mut stdout = get_stdout();
+    stdout.set_color(ColorSpec::new().set_fg(Some(Color::Blue)).set_bold(true))?;
+    writeln!(stdout, "{}  {}", DIRECTORY_ICON, path.display())?;
+    stdout.reset()?;
+    print_tree(&mut stdout, &root, "")
+}
+
+/// Extracts a single archive member into a temporary directory, keeping its
+/// file name so the normal viewer selection applies to it.
+pub fn extract_member(archive: &Path, member: &str) -> io::Result<(tempfile::TempDir, PathBuf)> {
+    let kind = ArchiveKind::detect(archive)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Unsupported archive"))?;
+    let data = read_member(archive, kind, member)?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} not found in {}", member, archive.display()),
+        )
+    })?;
+
+    let file_name = Path::new(member)
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid member path"))?;
+    let temp_dir = tempfile::TempDir::new()?;
+    let path = temp_dir.path().join(file_name);
+    fs::write(&path, data)?;
+    Ok((temp_dir, path))
+}
+
+fn list_entries(path: &Path, kind: ArchiveKind) -> io::Result<Vec<ArchiveEntry>> {
+    let mut entries = Vec::new();
+    match kind {
+        ArchiveKind::Zip => {
+            let mut archive = zip::ZipArchive::new(fs::File::open(path)?)?;
+            for i in 0..archive.len() {
+                let file = archive.by_index(i)?;
+                entries.push(ArchiveEntry {
+                    path: file.name().to_string(),
+                    size: file.size(),
+                    modified: file.last_modified().map(|time| {
+                        format!(
+                            "{:04}-{:02}-{:02} {:02}:{:02}",
+                            time.year(),
+                            time.month(),
+                            time.day(),
+                            time.hour(),
+                            time.minute()
+                        )
+                    }),
+                    is_dir: file.is_dir(),
+                });
+            }
+        }
+        _ => {
+            let mut archive = tar::Archive::new(open_tar(path, kind)?);
+            for entry in archive.entries()? {
+                let entry = entry?;
+                let header = entry.header();
+                entries.push(ArchiveEntry {
+                    path: entry.path()?.to_string_lossy().into_owned(),
+                    size: header.size()?,
+                    modified: header.mtime().ok().and_then(format_unix_time),
+                    is_dir: header.entry_type().is_dir(),
+                });
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn read_member(archive: &Path, kind: ArchiveKind, member: &str) -> io::Result<Option<Vec<u8>>> {
+    let mut data = Vec::new();
+    match kind {
+        ArchiveKind::Zip => {
+            let mut archive = zip::ZipArchive::new(fs::File::open(archive)?)?;
+            let result = archive.by_name(member);
+            match result {
+                Ok(mut file) => file.read_to_end(&mut data)?,
+                Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+                Err(e) => return Err(e.into()),
+            };
+        }
+        _ => {
+            let mut archive = tar::Archive::new(open_tar(archive, kind)?);
+            let mut found = false;
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                let path = entry.path()?.to_string_lossy().into_owned();
+                if path.trim_start_matches("./") == member {
+                    entry.read_to_end(&mut data)?;
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                return Ok(None);
+            }
+        }
+    }
+    Ok(Some(data))
+}
+
+fn open_tar(path: &Path, kind: ArchiveKind) -> io::Result<Box<dyn Read>> {
+    let file = fs::File::open(path)?;
+    Ok(match kind {
+        ArchiveKind::TarGz => Box::new(GzDecoder::new(file)),
+        ArchiveKind::TarBz2 => Box::new(BzDecoder::new(file)),
+        ArchiveKind::TarXz => Box::new(XzDecoder::new(file)),
+        ArchiveKind::TarZst => Box::new(zstd::Decoder::new(file)?),
+        _ => Box::new(file),
+    })
+}
+
+fn print_tree(stdout: &mut Box<dyn WriteColor>, node: &TreeNode, prefix: &str) -> io::Result<()> {
+    let mut children: Vec<_> = node.children.iter().collect();
+    children.sort_by_key(|(name, child)| (!child.is_dir(), name.to_string()));
+
+    for (index, (name, child)) in children.iter().enumerate() {
+        let is_last = index == children.len() - 1;
+        write!(
+            stdout,
+            "{}{}",
+            prefix,
+            if is_last {
+                TREE_LAST_BRANCH
+            } else {
+                TREE_BRANCH
+            }
+        )?;
+
+        if child.is_dir() {
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Blue)).set_bold(true))?;
+            writeln!(stdout, "{}  {}", DIRECTORY_ICON, name)?;
+            stdout.reset()?;
+            let child_prefix = format!(
+                "{}{}",
+                prefix,
+                if is_last { TREE_INDENT } else { TREE_VERTICAL }
+            );
+            print_tree(stdout, child, &child_prefix)?;
+        } else {
+            let file = File::new(Path::new(name.as_str()));
+            let icon = icon_for_file(&file, Some(Theme::Dark));
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::White)))?;
+            write!(stdout, "{}  {}", icon.icon, name)?;
+            if let Some(entry) = &child.entry {
+                stdout.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)).set_dimmed(true))?;
+                write!(stdout, "  {}", format_size(entry.size))?;
+                if let Some(modified) = &entry.modified {
+                    write!(stdout, "  {}", modified)?;
+                }
+            }
+            stdout.reset()?;
+            writeln!(stdout)?;
+        }
+    }
+    Ok(())
+}
+
+/// Formats seconds since the Unix epoch as a UTC `YYYY-MM-DD HH:MM` string,
+/// or `None` for times the calendar cannot represent.
+fn format_unix_time(seconds: u64) -> Option<String> {
+    let time = OffsetDateTime::from_unix_timestamp(i64::try_from(seconds).ok()?).ok()?;
+    Some(format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        time.year(),
+        u8::from(time.month()),
+        time.day(),
+        time.hour(),
+        time.minute()
+    ))
+}
@@ -0,0 +1,672 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io;
+
+use crate::app;
+use crate::render::render_markdown;
+use crate::utils::mdast::{self, ListEntry};
+
+const ADMONITIONS: [&str; 5] = ["NOTE", "TIP", "IMPORTANT", "WARNING", "CAUTION"];
+
+/// Delimiter lines of the blocks that run until a matching closing line.
+const DELIMITERS: [&str; 8] = ["----", "....", "====", "****", "____", "++++", "--", "|==="];
+
+const URL_SCHEMES: [&str; 3] = ["https://", "http://", "mailto:"];
+
+pub fn render_asciidoc(content: &str) -> io::Result<()> {
+    let mut json = asciidoc_to_mdast(content);
+    app::process_markdown_ast(&mut json);
+    render_markdown(&json)
+}
+
+/// Converts AsciiDoc to an mdast tree.
+pub fn asciidoc_to_mdast(content: &str) -> Value {
+    let lines: Vec<String> = content
+        .lines()
+        .map(|line| line.trim_end().to_string())
+        .collect();
+    let mut parser = Parser::default();
+    mdast::root(parser.blocks(&lines))
+}
+
+#[derive(Default)]
+struct Parser {
+    /// Document attributes from `:name: value` entries, for `{name}` references.
+    attributes: HashMap<String, String>,
+}
+
+/// The `[...]` attribute list and `.Title` line that may precede a block.
+#[derive(Default)]
+struct BlockMeta {
+    attributes: Vec<String>,
+    title: Option<String>,
+}
+
+impl BlockMeta {
+    fn style(&self) -> Option<&str> {
+        self.attributes.first().map(String::as_str)
+    }
+
+    fn named(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find_map(|attribute| {
+            let value = attribute.strip_prefix(name)?.strip_prefix('=')?;
+            Some(value.trim_matches('"'))
+        })
+    }
+}
+
+impl Parser {
+    fn blocks(&mut self, lines: &[String]) -> Vec<Value> {
+        let mut blocks = Vec::new();
+        let mut meta = BlockMeta::default();
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = lines[i].as_str();
+
+            if line.is_empty() || line.starts_with("[[") || line == "<<<" {
+                i += 1;
+                continue;
+            }
+            if line == "////" {
+                i = closing_delimiter(lines, i).map_or(lines.len(), |end| end + 1);
+                continue;
+            }
+            if line.starts_with("//") {
+                i += 1;
+                continue;
+            }
+            if let Some((name, value)) = attribute_entry(line) {
+                self.attributes.insert(name.to_string(), value.to_string());
+                i += 1;
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                meta.attributes = split_attributes(&line[1..line.len() - 1]);
+                i += 1;
+                continue;
+            }
+            if let Some(title) = block_title(line) {
+                meta.title = Some(title.to_string());
+                i += 1;
+                continue;
+            }
+
+            if let Some(title) = meta.title.take() {
+                blocks.push(mdast::paragraph(vec![mdast::strong(self.inline(&title))]));
+            }
+            let block_meta = std::mem::take(&mut meta);
+
+            if let Some((depth, title)) = heading(line) {
+                blocks.push(mdast::heading(depth, self.inline(title)));
+                i += 1;
+            } else if line == "'''" || line == "---" || line == "***" {
+                blocks.push(mdast::thematic_break());
+                i += 1;
+            } else if DELIMITERS.contains(&delimiter_kind(line)) {
+                let end = closing_delimiter(lines, i).unwrap_or(lines.len());
+                let body = &lines[i + 1..end.max(i + 1)];
+                blocks.extend(self.delimited_block(delimiter_kind(line), body, &block_meta));
+                i = end + 1;
+            } else if let Some(image) = line.strip_prefix("image::") {
+                let (target, alt) = macro_parts(image).unwrap_or((image, ""));
+                blocks.push(mdast::paragraph(vec![mdast::image(target, alt)]));
+                i += 1;
+            } else if list_marker(line).is_some() || description_term(line).is_some() {
+                let (list, next) = self.list(lines, i);
+                blocks.push(list);
+                i = next;
+            } else if line.starts_with(' ') {
+                // An indented paragraph is a literal block
+                let start = i;
+                while i < lines.len() && lines[i].starts_with(' ') {
+                    i += 1;
+                }
+                let body = dedent(&lines[start..i]);
+                blocks.push(mdast::code(None, &body.join("\n")));
+            } else {
+                let start = i;
+                while i < lines.len() && !lines[i].is_empty() && !starts_block(&lines[i]) {
+                    i += 1;
+                }
+                let text = join_lines(&lines[start..i.max(start + 1)]);
+                i = i.max(start + 1);
+
+                let admonition = block_meta
+                    .style()
+                    .filter(|style| ADMONITIONS.contains(style))
+                    .map(|style| (style, text.as_str()))
+                    .or_else(|| {
+                        let (label, content) = text.split_once(": ")?;
+                        ADMONITIONS.contains(&label).then_some((label, content))
+                    });
+                match admonition {
+                    Some((kind, content)) => {
+                        let content = self.plain_text(content);
+                        blocks.push(mdast::admonition(kind, &content));
+                    }
+                    None => blocks.push(mdast::paragraph(self.inline(&text))),
+                }
+            }
+        }
+
+        blocks
+    }
+
+    fn delimited_block(&mut self, kind: &str, body: &[String], meta: &BlockMeta) -> Vec<Value> {
+        match kind {
+            "----" | "...." => {
+                // `[source,rust]` or the `[,rust]` shorthand name the language
+                let language = meta
+                    .attributes
+                    .get(1)
+                    .filter(|_| kind == "----")
+                    .map(String::as_str)
+                    .filter(|language| !language.is_empty() && !language.contains('='));
+                vec![mdast::code(language, &body.join("\n"))]
+            }
+            "===="
+                if meta
+                    .style()
+                    .is_some_and(|style| ADMONITIONS.contains(&style)) =>
+            {
+                let content = self.plain_text(&join_lines(body));
+                vec![mdast::admonition(meta.style().unwrap_or("NOTE"), &content)]
+            }
+            "____" | "****" => vec![mdast::blockquote(self.blocks(body))],
+            "|===" => vec![self.table(body, meta)],
+            "++++" => Vec::new(),
+            _ => self.blocks(body),
+        }
+    }
+
+    fn table(&self, body: &[String], meta: &BlockMeta) -> Value {
+        let mut cells: Vec<String> = Vec::new();
+        let mut first_row_width = None;
+        for line in body {
+            if line.is_empty() {
+                if !cells.is_empty() {
+                    first_row_width.get_or_insert(cells.len());
+                }
+                continue;
+            }
+            match line.strip_prefix('|') {
+                Some(row) => {
+                    for cell in row.split('|') {
+                        cells.push(self.plain_text(cell.trim()));
+                    }
+                }
+                None => {
+                    if let Some(cell) = cells.last_mut() {
+                        cell.push(' ');
+                        cell.push_str(&self.plain_text(line.trim()));
+                    }
+                }
+            }
+        }
+
+        // `cols="1,2,3"` or `cols=3` give the width; otherwise the first row does
+        let columns = meta
+            .named("cols")
+            .map(|cols| match cols.parse::<usize>() {
+                Ok(count) => count,
+                Err(_) => cols.split(',').count(),
+            })
+            .or(first_row_width)
+            .unwrap_or(cells.len())
+            .max(1);
+
+        let rows = cells
+            .chunks(columns)
+            .map(|row| {
+                let mut row = row.to_vec();
+                row.resize(columns, String::new());
+                row
+            })
+            .collect();
+        mdast::table(rows)
+    }
+
+    fn list(&mut self, lines: &[String], start: usize) -> (Value, usize) {
+        let mut entries = Vec::new();
+        let mut top_kind = None;
+        let mut i = start;
+
+        while i < lines.len() {
+            let line = lines[i].as_str();
+
+            // A different kind of list at the top level starts a new list
+            let kind = list_marker(line)
+                .map(|(level, ordered, _)| (level, ordered, false))
+                .or_else(|| description_term(line).map(|(level, _, _)| (level, false, true)));
+            if let Some((level, ordered, is_description)) = kind {
+                let (top_level, top_ordered, top_description) =
+                    *top_kind.get_or_insert((level, ordered, is_description));
+                if level <= top_level && (ordered, is_description) != (top_ordered, top_description)
+                {
+                    break;
+                }
+            }
+
+            if let Some((level, ordered, text)) = list_marker(line) {
+                let (checked, text) = match text.get(..4) {
+                    Some("[x] " | "[*] ") => (Some(true), &text[4..]),
+                    Some("[ ] ") => (Some(false), &text[4..]),
+                    _ => (None, text),
+                };
+                let (text, next) = self.item_text(lines, i + 1, text);
+                entries.push(ListEntry {
+                    level,
+                    ordered,
+                    checked,
+                    children: vec![mdast::paragraph(self.inline(&text))],
+                });
+                i = next;
+            } else if let Some((level, term, definition)) = description_term(line) {
+                let (definition, next) = self.item_text(lines, i + 1, definition);
+                let mut body = Vec::new();
+                if !definition.is_empty() {
+                    body.push(mdast::paragraph(self.inline(&definition)));
+                }
+                let children = mdast::definition(vec![mdast::strong(self.inline(term))], body);
+                entries.push(ListEntry {
+                    level,
+                    ordered: false,
+                    checked: None,
+                    children,
+                });
+                i = next;
+            } else if line == "+" {
+                // A list continuation attaches the next block to the item
+                let end = next_block_end(lines, i + 1);
+                let blocks = self.blocks(&lines[i + 1..end]);
+                if let Some(entry) = entries.last_mut() {
+                    entry.children.extend(blocks);
+                }
+                i = end;
+            } else if line.is_empty() {
+                let next = lines[i..]
+                    .iter()
+                    .position(|line| !line.is_empty())
+                    .map_or(lines.len(), |offset| i + offset);
+                let continues = lines.get(next).is_some_and(|line| {
+                    list_marker(line).is_some() || description_term(line).is_some()
+                });
+                if !continues {
+                    break;
+                }
+                i = next;
+            } else {
+                break;
+            }
+        }
+
+        (mdast::nested_list(entries), i)
+    }
+
+    /// Joins an item's first line with the plain lines that continue it.
+    fn item_text(&self, lines: &[String], mut i: usize, first: &str) -> (String, usize) {
+        let mut text = first.to_string();
+        while let Some(line) = lines.get(i) {
+            if line.is_empty()
+                || line == "+"
+                || list_marker(line).is_some()
+                || description_term(line).is_some()
+                || starts_block(line)
+            {
+                break;
+            }
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(line.trim());
+            i += 1;
+        }
+        (text, i)
+    }
+
+    fn substitute(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            let reference = &rest[start + 1..];
+            match reference
+                .find('}')
+                .and_then(|end| Some((self.attributes.get(&reference[..end])?, end)))
+            {
+                Some((value, end)) => {
+                    result.push_str(value);
+                    rest = &reference[end + 1..];
+                }
+                None => {
+                    result.push('{');
+                    rest = reference;
+                }
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+
+    fn inline(&self, text: &str) -> Vec<Value> {
+        parse_inline(&self.substitute(text))
+    }
+
+    fn plain_text(&self, text: &str) -> String {
+        self.inline(text).iter().map(mdast::node_text).collect()
+    }
+}
+
+fn attribute_entry(line: &str) -> Option<(&str, &str)> {
+    let (name, value) = line.strip_prefix(':')?.split_once(':')?;
+    let is_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '!'));
+    is_name.then(|| (name, value.trim()))
+}
+
+/// Splits an attribute list on the commas outside quoted values.
+fn split_attributes(list: &str) -> Vec<String> {
+    let mut attributes = vec![String::new()];
+    let mut quoted = false;
+    for c in list.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => attributes.push(String::new()),
+            _ => {}
+        }
+        if c != ',' || quoted {
+            if let Some(attribute) = attributes.last_mut() {
+                attribute.push(c);
+            }
+        }
+    }
+    attributes
+        .iter()
+        .map(|attribute| attribute.trim().to_string())
+        .collect()
+}
+
+fn block_title(line: &str) -> Option<&str> {
+    let title = line.strip_prefix('.')?;
+    title
+        .starts_with(|c: char| !c.is_whitespace() && c != '.')
+        .then_some(title)
+}
+
+/// Parses `== Title`, or the Markdown-style `## Title` AsciiDoc also accepts.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let marker = line.chars().next().filter(|c| *c == '=' || *c == '#')?;
+    let depth = line.chars().take_while(|c| *c == marker).count();
+    let title = line[depth..].strip_prefix(' ')?;
+    (depth <= 6).then(|| (depth, title.trim()))
+}
+
+/// Reduces a delimiter line to its canonical form, so `------` matches `----`.
+fn delimiter_kind(line: &str) -> &str {
+    if line == "--" || line == "|===" {
+        return line;
+    }
+    let Some(first) = line.chars().next() else {
+        return "";
+    };
+    if line.len() >= 4 && line.chars().all(|c| c == first) {
+        DELIMITERS
+            .iter()
+            .find(|delimiter| delimiter.starts_with(first))
+            .copied()
+            .unwrap_or("")
+    } else {
+        ""
+    }
+}
+
+fn closing_delimiter(lines: &[String], start: usize) -> Option<usize> {
+    let opening = &lines[start];
+    lines[start + 1..]
+        .iter()
+        .position(|line| line == opening)
+        .map(|offset| start + 1 + offset)
+}
+
+/// Returns where the block starting at `start` ends: after its closing
+/// delimiter for delimited blocks, or at the next blank line.
+fn next_block_end(lines: &[String], start: usize) -> usize {
+    match lines.get(start) {
+        Some(line) if DELIMITERS.contains(&delimiter_kind(line)) => {
+            closing_delimiter(lines, start).map_or(lines.len(), |end| end + 1)
+        }
+        _ => lines[start..]
+            .iter()
+            .position(|line| line.is_empty())
+            .map_or(lines.len(), |offset| start + offset),
+    }
+}
+
+fn starts_block(line: &str) -> bool {
+    heading(line).is_some()
+        || list_marker(line).is_some()
+        || DELIMITERS.contains(&delimiter_kind(line))
+        || line.starts_with("image::")
+        || (line.starts_with('[') && line.ends_with(']'))
+        || line.starts_with("//")
+}
+
+/// Returns the nesting level, whether the list is ordered, and the item text.
+fn list_marker(line: &str) -> Option<(usize, bool, &str)> {
+    if let Some(text) = line.strip_prefix("- ") {
+        return Some((1, false, text));
+    }
+    if let Some(marker) = line.chars().next().filter(|c| *c == '*' || *c == '.') {
+        let level = line.chars().take_while(|c| *c == marker).count();
+        let text = line[level..].strip_prefix(' ')?;
+        return Some((level, marker == '.', text.trim_start()));
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let text = line.get(digits..)?.strip_prefix(". ")?;
+    (digits > 0).then(|| (1, true, text.trim_start()))
+}
+
+/// Parses `term:: definition`, returning the level (one per extra colon), the
+/// term and the definition, which may be empty.
+fn description_term(line: &str) -> Option<(usize, &str, &str)> {
+    let start = line.find("::")?;
+    let colons = line[start..].chars().take_while(|c| *c == ':').count();
+    let rest = &line[start + colons..];
+    if start == 0 || !(rest.is_empty() || rest.starts_with(' ')) || line.starts_with("image:") {
+        return None;
+    }
+    Some((colons - 1, line[..start].trim(), rest.trim()))
+}
+
+fn dedent(lines: &[String]) -> Vec<String> {
+    let common = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| line.get(common..).unwrap_or("").to_string())
+        .collect()
+}
+
+/// Joins paragraph lines, dropping the ` +` hard line break markers.
+fn join_lines(lines: &[String]) -> String {
+    lines
+        .iter()
+        .map(|line| line.trim().trim_end_matches(" +"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Splits the `target[text]` of an inline or block macro.
+fn macro_parts(text: &str) -> Option<(&str, &str)> {
+    let open = text.find('[')?;
+    let close = text[open..].find(']')? + open;
+    Some((&text[..open], &text[open + 1..close]))
+}
+
+/// Constrained markup like `*bold*` must sit at word boundaries.
+fn can_open(previous: Option<char>) -> bool {
+    previous.is_none_or(|c| !c.is_alphanumeric() && c != '\\')
+}
+
+fn parse_inline(text: &str) -> Vec<Value> {
+    let mut nodes = Vec::new();
+    let mut buffer = String::new();
+    let mut rest = text;
+    let mut previous = None;
+
+    let flush = |buffer: &mut String, nodes: &mut Vec<Value>| {
+        if !buffer.is_empty() {
+            nodes.push(mdast::text(buffer));
+            buffer.clear();
+        }
+    };
+
+    while let Some(c) = rest.chars().next() {
+        let markup = inline_markup(rest, previous);
+        if let Some((node, length)) = markup {
+            flush(&mut buffer, &mut nodes);
+            nodes.push(node);
+            previous = rest[..length].chars().last();
+            rest = &rest[length..];
+            continue;
+        }
+
+        buffer.push(c);
+        rest = &rest[c.len_utf8()..];
+        previous = Some(c);
+    }
+
+    flush(&mut buffer, &mut nodes);
+    nodes
+}
+
+/// Recognises the inline markup at the start of `text`, returning its node
+/// and length.
+fn inline_markup(text: &str, previous: Option<char>) -> Option<(Value, usize)> {
+    let opens = can_open(previous);
+
+    for (delimiter, constrained) in [("**", false), ("__", false), ("*", true), ("_", true)] {
+        let Some(inner) = text.strip_prefix(delimiter) else {
+            continue;
+        };
+        if constrained && (!opens || inner.starts_with(char::is_whitespace)) {
+            continue;
+        }
+        let end = inner.find(delimiter).filter(|&end| end > 0)?;
+        let children = parse_inline(&inner[..end]);
+        let node = if delimiter.starts_with('*') {
+            mdast::strong(children)
+        } else {
+            mdast::emphasis(children)
+        };
+        return Some((node, end + 2 * delimiter.len()));
+    }
+
+    if let Some(inner) = text.strip_prefix('`').filter(|_| opens) {
+        let end = inner.find('`').filter(|&end| end > 0)?;
+        let code = &inner[..end];
+        // `+text+` turns off substitutions inside the code
+        let code = code
+            .strip_prefix('+')
+            .and_then(|code| code.strip_suffix('+'))
+            .unwrap_or(code);
+        return Some((mdast::inline_code(code), end + 2));
+    }
+
+    if let Some(inner) = text.strip_prefix("<<") {
+        let end = inner.find(">>")?;
+        let reference = &inner[..end];
+        let label = reference
+            .split_once(',')
+            .map_or(reference, |(_, label)| label)
+            .trim();
+        return Some((mdast::emphasis(vec![mdast::text(label)]), end + 4));
+    }
+
+    if !opens {
+        return None;
+    }
+
+    for (prefix, macro_name) in [("image:", "image"), ("link:", "link"), ("xref:", "xref")] {
+        if let Some(inner) = text.strip_prefix(prefix) {
+            let (target, label) = macro_parts(inner)?;
+            if target.contains(char::is_whitespace) {
+                return None;
+            }
+            let node = match macro_name {
+                "image" => mdast::image(target, label),
+                "link" => link(target, label),
+                _ => mdast::emphasis(vec![mdast::text(if label.is_empty() {
+                    target
+                } else {
+                    label
+                })]),
+            };
+            return Some((node, prefix.len() + target.len() + label.len() + 2));
+        }
+    }
+
+    if let Some(inner) = text.strip_prefix("kbd:[") {
+        let end = inner.find(']')?;
+        return Some((mdast::inline_code(&inner[..end]), end + 6));
+    }
+
+    if URL_SCHEMES.iter().any(|scheme| text.starts_with(scheme)) {
+        let end = text
+            .find(|c: char| c.is_whitespace() || c == '[')
+            .unwrap_or(text.len());
+        let url = text[..end].trim_end_matches(['.', ',', ')', ';']);
+        if text[url.len()..].starts_with('[') {
+            let (_, label) = macro_parts(text)?;
+            return Some((link(url, label), url.len() + label.len() + 2));
+        }
+        return Some((link(url, ""), url.len()));
+    }
+
+    None
+}
+
+fn link(url: &str, label: &str) -> Value {
+    // An unlabelled link shows its URL, which must not be parsed as a link again
+    if label.is_empty() {
+        return mdast::link(url, vec![mdast::text(url)]);
+    }
+    mdast::link(url, parse_inline(label))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_link(content: &str) -> Value {
+        let document = asciidoc_to_mdast(content);
+        document["children"][0]["children"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|node| node["type"] == "link")
+            .cloned()
+            .unwrap()
+    }
+
+    #[test]
+    fn bare_url_links_to_itself() {
+        let link = first_link("http://a");
+        assert_eq!(link["url"], "http://a");
+        assert_eq!(mdast::node_text(&link), "http://a");
+    }
+
+    #[test]
+    fn link_macro_without_label_shows_url() {
+        let link = first_link("see link:http://x[] here");
+        assert_eq!(link["url"], "http://x");
+        assert_eq!(mdast::node_text(&link), "http://x");
+    }
+}
@@ -0,0 +1,145 @@
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use xz2::read::XzDecoder;
+
+use crate::archive::is_archive;
+use crate::utils::read_head;
+
+/// Enough decompressed data for every check that picks a viewer.
+const SNIFFED_SIZE: u64 = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    /// Detects a compressed file by its extension, or by its magic bytes when
+    /// the name gives nothing away. Compressed tarballs are left to the
+    /// archive viewer, which lists their contents.
+    pub fn detect(path: &Path) -> Option<Self> {
+        if !path.is_file() || is_archive(path) {
+            return None;
+        }
+        let extension = path
+            .extension()
+            .and_then(std::ffi::OsStr::to_str)
+            .unwrap_or("")
+            .to_lowercase();
+        let by_extension = match extension.as_str() {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "bz2" | "bzip2" => Some(Compression::Bzip2),
+            "xz" => Some(Compression::Xz),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            _ => None,
+        };
+        by_extension.or_else(|| Self::from_magic(&read_head(path).ok()?))
+    }
+
+    fn from_magic(head: &[u8]) -> Option<Self> {
+        if head.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if head.starts_with(b"BZh") && is_bzip2_block(head.get(4..10)) {
+            Some(Compression::Bzip2)
+        } else if head.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else if head.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    fn decoder(self, file: BufReader<File>) -> io::Result<Box<dyn Read>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(MultiGzDecoder::new(file)),
+            Compression::Bzip2 => Box::new(MultiBzDecoder::new(file)),
+            Compression::Xz => Box::new(XzDecoder::new_multi_decoder(file)),
+            Compression::Zstd => Box::new(zstd::Decoder::with_buffer(file)?),
+        })
+    }
+}
+
+/// `BZh` is plain text, so bzip2 data is only recognised by the block or
+/// end-of-stream marker that follows the header.
+fn is_bzip2_block(marker: Option<&[u8]>) -> bool {
+    matches!(
+        marker,
+        Some([0x31, 0x41, 0x59, 0x26, 0x53, 0x59] | [0x17, 0x72, 0x45, 0x38, 0x50, 0x90])
+    )
+}
+
+/// Opens a file for reading, decompressing it as it is read when it is
+/// compressed.
+pub fn open_decompressed(path: &Path) -> io::Result<Box<dyn Read>> {
+    let file = BufReader::new(File::open(path)?);
+    match Compression::detect(path) {
+        Some(compression) => compression.decoder(file),
+        None => Ok(Box::new(file)),
+    }
+}
+
+/// The path a file had before compression, like `CHANGELOG.md` for
+/// `CHANGELOG.md.gz`, which its language is detected from.
+pub fn inner_path(path: &Path) -> PathBuf {
+    path.with_file_name(inner_file_name(path))
+}
+
+/// Decompresses a file into a temporary directory under the name it had
+/// before compression, for the viewers that read files themselves. The data
+/// is streamed to disk rather than held in memory.
+pub fn decompress(path: &Path, compression: Compression) -> io::Result<(TempDir, PathBuf)> {
+    decompress_to_temp_dir(path, compression, u64::MAX)
+}
+
+/// Decompresses just enough of a file to pick a viewer from its name and
+/// content, so `CHANGELOG.md.gz` is shown as Markdown.
+pub fn decompress_head(path: &Path, compression: Compression) -> io::Result<(TempDir, PathBuf)> {
+    decompress_to_temp_dir(path, compression, SNIFFED_SIZE)
+}
+
+fn decompress_to_temp_dir(
+    path: &Path,
+    compression: Compression,
+    limit: u64,
+) -> io::Result<(TempDir, PathBuf)> {
+    let file = BufReader::new(File::open(path)?);
+    let mut decoder = compression.decoder(file)?.take(limit);
+
+    let temp_dir = TempDir::new()?;
+    let inner_path = temp_dir.path().join(inner_file_name(path));
+    let mut output = BufWriter::new(File::create(&inner_path)?);
+    io::copy(&mut decoder, &mut output).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Failed to decompress {}: {}", path.display(), e),
+        )
+    })?;
+    output.into_inner().map_err(io::Error::other)?;
+    Ok((temp_dir, inner_path))
+}
+
+/// The file name without its compression extension. Files that were only
+/// recognised by their content keep their name.
+fn inner_file_name(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "decompressed".to_string());
+    let Some((stem, extension)) = name.rsplit_once('.') else {
+        return name;
+    };
+    match extension.to_lowercase().as_str() {
+        "gz" | "gzip" | "bz2" | "bzip2" | "xz" | "zst" | "zstd" if !stem.is_empty() => {
+            stem.to_string()
+        }
+        _ => name,
+    }
+}
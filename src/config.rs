@@ -9,6 +9,10 @@ use std::sync::OnceLock;
 
 use crate::app::{AppState, APP_STATE};
 use crate::constants::DOCS_DIR;
+use crate::graphics::GraphicsProtocol;
+use crate::line_range::LineRange;
+use crate::log::Level;
+use crate::utils::{clear_image_cache, format_size};
 
 static CONFIG: OnceLock<AppConfig> = OnceLock::new();
 
@@ -24,6 +28,66 @@ pub struct AppConfig {
     pub debug_mode: bool,
     pub use_colors: bool,
     pub convert_html: bool,
+    #[serde(default)]
+    pub hex_offset: Option<u64>,
+    #[serde(default)]
+    pub hex_length: Option<u64>,
+    #[serde(default)]
+    pub force_render: bool,
+    #[serde(default)]
+    pub diff_mode: bool,
+    #[serde(default)]
+    pub diff_unified: bool,
+    #[serde(default)]
+    pub diff_rendered: bool,
+    #[serde(default)]
+    pub sqlite_table: Option<String>,
+    #[serde(default)]
+    pub sqlite_limit: Option<usize>,
+    #[serde(default)]
+    pub log_level: Option<String>,
+    #[serde(default)]
+    pub viewer: Option<String>,
+    #[serde(default)]
+    pub animate: bool,
+    #[serde(default)]
+    pub animation_loops: Option<u32>,
+    #[serde(default)]
+    pub animation_seconds: Option<u64>,
+    #[serde(default)]
+    pub frame: Option<usize>,
+    #[serde(default)]
+    pub image_info: bool,
+    #[serde(default)]
+    pub graphics_protocol: Option<String>,
+    #[serde(default)]
+    pub offline: bool,
+    #[serde(default)]
+    pub image_cache_size: Option<u64>,
+    #[serde(default)]
+    pub image_max_size: Option<u64>,
+    #[serde(default)]
+    pub image_timeout: Option<u64>,
+    #[serde(default)]
+    pub image_connect_timeout: Option<u64>,
+    #[serde(default)]
+    pub document_max_size: Option<u64>,
+    #[serde(default)]
+    pub document_timeout: Option<u64>,
+    #[serde(default)]
+    pub encoding: Option<String>,
+    #[serde(default)]
+    pub follow: bool,
+    #[serde(default)]
+    pub watch: bool,
+    #[serde(default)]
+    pub line_range: Option<String>,
+    #[serde(default)]
+    pub highlight_lines: Vec<usize>,
+    #[serde(default)]
+    pub context: Option<usize>,
+    #[serde(default = "default_true")]
+    pub show_git_changes: bool,
 }
 
 impl AppConfig {
@@ -61,6 +125,36 @@ impl AppConfig {
             debug_mode: false,
             use_colors: true,
             convert_html: true,
+            hex_offset: None,
+            hex_length: None,
+            force_render: false,
+            diff_mode: false,
+            diff_unified: false,
+            diff_rendered: false,
+            sqlite_table: None,
+            sqlite_limit: None,
+            log_level: None,
+            viewer: None,
+            animate: false,
+            animation_loops: None,
+            animation_seconds: None,
+            frame: None,
+            image_info: false,
+            graphics_protocol: None,
+            offline: false,
+            image_cache_size: None,
+            image_max_size: None,
+            image_timeout: None,
+            image_connect_timeout: None,
+            document_max_size: None,
+            document_timeout: None,
+            encoding: None,
+            follow: false,
+            watch: false,
+            line_range: None,
+            highlight_lines: Vec::new(),
+            context: None,
+            show_git_changes: true,
         }
     }
 
@@ -89,7 +183,7 @@ pub fn get_config() -> &'static AppConfig {
 pub fn initialize_app() -> io::Result<(AppConfig, Option<Vec<PathBuf>>)> {
     let (mut config, file_paths) = parse_cli_args()?;
 
-    if !std::io::stdout().is_terminal() {
+    if !std::io::stdout().is_terminal() && !config.force_render {
         config.use_colors = false;
     }
 
@@ -105,6 +199,7 @@ pub fn initialize_app() -> io::Result<(AppConfig, Option<Vec<PathBuf>>)> {
     Ok((config, file_paths))
 }
 
+#[allow(clippy::map_clone, clippy::manual_strip)]
 fn parse_cli_args() -> io::Result<(AppConfig, Option<Vec<PathBuf>>)> {
     let args: Vec<String> = env::args().collect();
     let mut config = AppConfig::default();
@@ -129,7 +224,76 @@ fn parse_cli_args() -> io::Result<(AppConfig, Option<Vec<PathBuf>>)> {
                 }
                 "convert-html" => config.convert_html = parse_bool(parts.get(1).map(|s| *s)),
                 "show-filename" => config.show_filename = parse_bool(parts.get(1).map(|s| *s)),
+                "show-git-changes" => {
+                    config.show_git_changes = parse_bool(parts.get(1).map(|s| *s))
+                }
                 "use-colors" => config.use_colors = parse_bool(parts.get(1).map(|s| *s)),
+                "offset" => config.hex_offset = parse_u64(parts.get(1).map(|s| *s)),
+                "length" => config.hex_length = parse_u64(parts.get(1).map(|s| *s)),
+                "force-render" => config.force_render = parse_bool(parts.get(1).map(|s| *s)),
+                "diff" => config.diff_mode = parse_bool(parts.get(1).map(|s| *s)),
+                "unified" => config.diff_unified = parse_bool(parts.get(1).map(|s| *s)),
+                "diff-rendered" => config.diff_rendered = parse_bool(parts.get(1).map(|s| *s)),
+                "table" => config.sqlite_table = parts.get(1).map(|s| s.to_string()),
+                "viewer" => config.viewer = parts.get(1).map(|s| s.to_string()),
+                "level" => config.log_level = parts.get(1).map(|s| s.to_string()),
+                "limit" => {
+                    config.sqlite_limit = parse_u64(parts.get(1).map(|s| *s)).map(|n| n as usize)
+                }
+                "animate" => config.animate = parse_bool(parts.get(1).map(|s| *s)),
+                "loops" => config.animation_loops = parse_u32(parts.get(1).map(|s| *s)),
+                "animation-seconds" => {
+                    config.animation_seconds = parse_u64(parts.get(1).map(|s| *s))
+                }
+                "frame" => config.frame = parse_u64(parts.get(1).map(|s| *s)).map(|n| n as usize),
+                "image-info" => config.image_info = parse_bool(parts.get(1).map(|s| *s)),
+                "graphics-protocol" => {
+                    config.graphics_protocol = parts.get(1).map(|s| s.to_string())
+                }
+                "offline" => config.offline = parse_bool(parts.get(1).map(|s| *s)),
+                "image-cache-size" => config.image_cache_size = parse_u64(parts.get(1).map(|s| *s)),
+                "image-max-size" => config.image_max_size = parse_u64(parts.get(1).map(|s| *s)),
+                "image-timeout" => config.image_timeout = parse_u64(parts.get(1).map(|s| *s)),
+                "image-connect-timeout" => {
+                    config.image_connect_timeout = parse_u64(parts.get(1).map(|s| *s))
+                }
+                "document-max-size" => {
+                    config.document_max_size = parse_u64(parts.get(1).map(|s| *s))
+                }
+                "document-timeout" => config.document_timeout = parse_u64(parts.get(1).map(|s| *s)),
+                "encoding" => config.encoding = parts.get(1).map(|s| s.to_string()),
+                "follow" => config.follow = parse_bool(parts.get(1).map(|s| *s)),
+                "watch" => config.watch = parse_bool(parts.get(1).map(|s| *s)),
+                "line-range" => config.line_range = parts.get(1).map(|s| s.to_string()),
+                "highlight-line" => {
+                    let lines = parts.get(1).map(|s| *s).unwrap_or_default().split(',');
+                    for line in lines {
+                        match line.trim().parse() {
+                            Ok(line) if line > 0 => config.highlight_lines.push(line),
+                            _ => {
+                                return Err(io::Error::new(
+                                    io::ErrorKind::InvalidInput,
+                                    format!("Invalid line number '{}' for --highlight-line", line),
+                                ))
+                            }
+                        }
+                    }
+                }
+                "context" => {
+                    config.context = parse_u64(parts.get(1).map(|s| *s)).map(|n| n as usize)
+                }
+                "clear-cache" => {
+                    match clear_image_cache() {
+                        Ok((count, size)) => {
+                            println!("Removed {} cached images ({})", count, format_size(size))
+                        }
+                        Err(e) => {
+                            eprintln!("Error clearing the image cache: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                    std::process::exit(0);
+                }
                 "config" => {
                     if let Some(path) = parts.get(1) {
                         if let Ok(file_config) = AppConfig::load_from_file(Path::new(path)) {
@@ -167,6 +331,51 @@ fn parse_cli_args() -> io::Result<(AppConfig, Option<Vec<PathBuf>>)> {
         i += 1;
     }
 
+    if let Some(name) = &config.graphics_protocol {
+        if GraphicsProtocol::from_name(name).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Unknown graphics protocol '{}', expected one of auto, kitty, iterm, sixel, halfblocks, ascii or none",
+                    name
+                ),
+            ));
+        }
+    }
+
+    if let Some(label) = &config.encoding {
+        if encoding_rs::Encoding::for_label(label.as_bytes()).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown encoding '{}'", label),
+            ));
+        }
+    }
+
+    if let Some(level) = &config.log_level {
+        if Level::from_str(level).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Unknown log level '{}', expected one of trace, debug, info, warn, error or fatal",
+                    level
+                ),
+            ));
+        }
+    }
+
+    if let Some(spec) = &config.line_range {
+        if LineRange::parse(spec).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Invalid line range '{}', expected START:END, START:, :END or a line number",
+                    spec
+                ),
+            ));
+        }
+    }
+
     let file_paths = if file_paths.is_empty() {
         None
     } else {
@@ -176,6 +385,11 @@ fn parse_cli_args() -> io::Result<(AppConfig, Option<Vec<PathBuf>>)> {
     Ok((config, file_paths))
 }
 
+/// Options that are on unless a config file turns them off.
+fn default_true() -> bool {
+    true
+}
+
 fn parse_bool(value: Option<&str>) -> bool {
     match value {
         Some(v) => match v.to_lowercase().as_str() {
@@ -191,6 +405,13 @@ fn parse_u32(value: Option<&str>) -> Option<u32> {
     value.and_then(|v| v.parse().ok())
 }
 
+fn parse_u64(value: Option<&str>) -> Option<u64> {
+    value.and_then(|v| match v.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => v.parse().ok(),
+    })
+}
+
 fn render_help() -> io::Result<()> {
     if let Some(file) = DOCS_DIR.get_file("main.md") {
         let content = file
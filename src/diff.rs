@@ -0,0 +1,470 @@
+use similar::{ChangeTag, TextDiff};
+use std::env;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::Command;
+use termcolor::{Color, ColorSpec, WriteColor};
+
+use crate::config::get_config;
+use crate::render::get_stdout;
+use crate::utils::{detect_language, highlight_code, read_text_file};
+
+const LINE_NUMBER_WIDTH: usize = 4;
+const CONTEXT_LINES: usize = 3;
+const DEFAULT_TERMINAL_WIDTH: usize = 120;
+
+/// Returns true when `content` looks like unified diff output.
+pub fn looks_like_diff(content: &str) -> bool {
+    if content.starts_with("diff --git ") {
+        return true;
+    }
+    let mut lines = content.lines().skip_while(|line| !line.starts_with("--- "));
+    lines.next().is_some()
+        && lines.next().is_some_and(|line| line.starts_with("+++ "))
+        && lines.next().is_some_and(|line| line.starts_with("@@ "))
+}
+
+#[derive(Default)]
+struct Hunk {
+    old_line: usize,
+    new_line: usize,
+    old_remaining: usize,
+    new_remaining: usize,
+}
+
+impl Hunk {
+    fn is_open(&self) -> bool {
+        self.old_remaining > 0 || self.new_remaining > 0
+    }
+}
+
+pub fn render_diff(content: &str) -> io::Result<()> {
+    let mut stdout = get_stdout();
+    let mut language = "txt".to_string();
+    let mut hunk = Hunk::default();
+
+    for line in content.lines() {
+        let hunk_line = if !hunk.is_open() {
+            None
+        } else if line.is_empty() {
+            // Some tools drop the leading space of blank context lines
+            Some((" ", ""))
+        } else {
+            line.get(..1).zip(line.get(1..))
+        };
+
+        match hunk_line {
+            Some(("+", code)) => {
+                write_gutter(&mut stdout, None, Some(hunk.new_line), '+', Color::Green)?;
+                write_code(&mut stdout, code, &language)?;
+                hunk.new_line += 1;
+                hunk.new_remaining = hunk.new_remaining.saturating_sub(1);
+            }
+            Some(("-", code)) => {
+                write_gutter(&mut stdout, Some(hunk.old_line), None, '-', Color::Red)?;
+                write_code(&mut stdout, code, &language)?;
+                hunk.old_line += 1;
+                hunk.old_remaining = hunk.old_remaining.saturating_sub(1);
+            }
+            Some((" ", code)) => {
+                write_gutter(
+                    &mut stdout,
+                    Some(hunk.old_line),
+                    Some(hunk.new_line),
+                    ' ',
+                    Color::White,
+                )?;
+                write_code(&mut stdout, code, &language)?;
+                hunk.old_line += 1;
+                hunk.new_line += 1;
+                hunk.old_remaining = hunk.old_remaining.saturating_sub(1);
+                hunk.new_remaining = hunk.new_remaining.saturating_sub(1);
+            }
+            _ => {
+                if let Some(header) = render_header_line(&mut stdout, line, &mut language)? {
+                    hunk = header;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders a line outside of a hunk body, returning the new hunk state when
+/// the line is a hunk header.
+fn render_header_line(
+    stdout: &mut Box<dyn WriteColor>,
+    line: &str,
+    language: &mut String,
+) -> io::Result<Option<Hunk>> {
+    let mut hunk = None;
+    let mut spec = ColorSpec::new();
+
+    if line.starts_with("diff ") {
+        writeln!(stdout)?;
+        spec.set_fg(Some(Color::Yellow)).set_bold(true);
+    } else if let Some(path) = line.strip_prefix("+++ ") {
+        if let Some(detected) = language_for_header(path) {
+            *language = detected;
+        }
+        spec.set_fg(Some(Color::Green)).set_bold(true);
+    } else if let Some(path) = line.strip_prefix("--- ") {
+        if let Some(detected) = language_for_header(path) {
+            *language = detected;
+        }
+        spec.set_fg(Some(Color::Red)).set_bold(true);
+    } else if line.starts_with("@@") {
+        hunk = parse_hunk_header(line);
+        spec.set_fg(Some(Color::Cyan));
+    } else {
+        // Extended headers ("index", "new file mode", "\ No newline", ...)
+        spec.set_fg(Some(Color::White)).set_dimmed(true);
+    }
+
+    stdout.set_color(&spec)?;
+    writeln!(stdout, "{}", line)?;
+    stdout.reset()?;
+    Ok(hunk)
+}
+
+/// Detects the language of a `---`/`+++` header path such as `a/src/main.rs`.
+fn language_for_header(header: &str) -> Option<String> {
+    let path = header.split('\t').next().unwrap_or(header).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(detect_language(path))
+}
+
+/// Parses `@@ -old_start,old_len +new_start,new_len @@`.
+fn parse_hunk_header(line: &str) -> Option<Hunk> {
+    let mut ranges = line.trim_start_matches('@').split_whitespace();
+    let (old_line, old_remaining) = parse_range(ranges.next()?.strip_prefix('-')?)?;
+    let (new_line, new_remaining) = parse_range(ranges.next()?.strip_prefix('+')?)?;
+    Some(Hunk {
+        old_line,
+        new_line,
+        old_remaining,
+        new_remaining,
+    })
+}
+
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+fn write_gutter(
+    stdout: &mut Box<dyn WriteColor>,
+    old_line: Option<usize>,
+    new_line: Option<usize>,
+    marker: char,
+    color: Color,
+) -> io::Result<()> {
+    let format_number = |number: Option<usize>| match number {
+        Some(n) => format!("{:>width$}", n, width = LINE_NUMBER_WIDTH),
+        None => " ".repeat(LINE_NUMBER_WIDTH),
+    };
+
+    if get_config().show_line_numbers {
+        stdout.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+        write!(
+            stdout,
+            "{} {} │",
+            format_number(old_line),
+            format_number(new_line)
+        )?;
+        stdout.reset()?;
+    }
+    stdout.set_color(ColorSpec::new().set_fg(Some(color)).set_bold(true))?;
+    write!(stdout, "{} ", marker)?;
+    stdout.reset()?;
+    Ok(())
+}
+
+fn write_code(stdout: &mut Box<dyn WriteColor>, code: &str, language: &str) -> io::Result<()> {
+    let language = if get_config().use_colors {
+        language
+    } else {
+        "txt"
+    };
+    if let Err(e) = highlight_code(code, language, stdout) {
+        // If highlighting fails, fall back to plain text
+        write!(stdout, "{}", code)?;
+        eprintln!(
+            "Error highlighting code: {}. Falling back to plain text for this line.",
+            e
+        );
+    }
+    stdout.reset()?;
+    writeln!(stdout)?;
+    Ok(())
+}
+
+/// Computes a line diff between two files and renders it side by side, or as
+/// a unified diff when `--unified` is given.
+pub fn render_file_comparison(old_path: &Path, new_path: &Path) -> io::Result<()> {
+    let config = get_config();
+    let is_markdown = [old_path, new_path]
+        .iter()
+        .all(|path| path.extension().is_some_and(|ext| ext == "md"));
+    if config.diff_rendered && !is_markdown {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--diff-rendered only compares Markdown files",
+        ));
+    }
+
+    let (old, new, language) = if config.diff_rendered {
+        (
+            render_to_text(old_path)?,
+            render_to_text(new_path)?,
+            "txt".to_string(),
+        )
+    } else {
+        (
+            read_text_file(old_path)?,
+            read_text_file(new_path)?,
+            detect_language(&new_path.to_string_lossy()),
+        )
+    };
+
+    let mut stdout = get_stdout();
+    stdout.set_color(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(true))?;
+    writeln!(stdout, "--- {}", old_path.display())?;
+    stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)).set_bold(true))?;
+    writeln!(stdout, "+++ {}", new_path.display())?;
+    stdout.reset()?;
+
+    let diff = TextDiff::from_lines(&old, &new);
+    for (index, group) in diff.grouped_ops(CONTEXT_LINES).iter().enumerate() {
+        if index > 0 {
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+            writeln!(stdout, "⋮")?;
+            stdout.reset()?;
+        }
+        for op in group {
+            let changes: Vec<_> = diff.iter_changes(op).collect();
+            if config.diff_unified {
+                for change in changes {
+                    let code = change.value().trim_end_matches(['\r', '\n']);
+                    let old_line = change.old_index().map(|i| i + 1);
+                    let new_line = change.new_index().map(|i| i + 1);
+                    let (marker, color) = match change.tag() {
+                        ChangeTag::Delete => ('-', Color::Red),
+                        ChangeTag::Insert => ('+', Color::Green),
+                        ChangeTag::Equal => (' ', Color::White),
+                    };
+                    write_gutter(&mut stdout, old_line, new_line, marker, color)?;
+                    write_code(&mut stdout, code, &language)?;
+                }
+            } else {
+                render_side_by_side(&mut stdout, &changes, &language)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Pairs deleted lines with the inserted lines that follow them so that
+/// replacements show up on the same row.
+fn render_side_by_side(
+    stdout: &mut Box<dyn WriteColor>,
+    changes: &[similar::Change<&str>],
+    language: &str,
+) -> io::Result<()> {
+    let column_width = side_by_side_column_width();
+    let deletes: Vec<_> = changes
+        .iter()
+        .filter(|c| c.tag() == ChangeTag::Delete)
+        .collect();
+    let inserts: Vec<_> = changes
+        .iter()
+        .filter(|c| c.tag() == ChangeTag::Insert)
+        .collect();
+
+    if deletes.is_empty() && inserts.is_empty() {
+        for change in changes {
+            let left = Side::from_change(change, change.old_index());
+            let right = Side::from_change(change, change.new_index());
+            write_row(stdout, Some(left), Some(right), column_width, language)?;
+        }
+        return Ok(());
+    }
+
+    for row in 0..deletes.len().max(inserts.len()) {
+        let left = deletes
+            .get(row)
+            .map(|change| Side::from_change(change, change.old_index()));
+        let right = inserts
+            .get(row)
+            .map(|change| Side::from_change(change, change.new_index()));
+        write_row(stdout, left, right, column_width, language)?;
+    }
+    Ok(())
+}
+
+struct Side {
+    line_number: usize,
+    code: String,
+    tag: ChangeTag,
+}
+
+impl Side {
+    fn from_change(change: &similar::Change<&str>, index: Option<usize>) -> Self {
+        Side {
+            line_number: index.map(|i| i + 1).unwrap_or(0),
+            code: change
+                .value()
+                .trim_end_matches(['\r', '\n'])
+                .replace('\t', "    "),
+            tag: change.tag(),
+        }
+    }
+}
+
+fn write_row(
+    stdout: &mut Box<dyn WriteColor>,
+    left: Option<Side>,
+    right: Option<Side>,
+    column_width: usize,
+    language: &str,
+) -> io::Result<()> {
+    write_side(stdout, left.as_ref(), column_width, language)?;
+    stdout.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+    write!(stdout, " ┃ ")?;
+    stdout.reset()?;
+    write_side(stdout, right.as_ref(), column_width, language)?;
+    writeln!(stdout)?;
+    Ok(())
+}
+
+fn write_side(
+    stdout: &mut Box<dyn WriteColor>,
+    side: Option<&Side>,
+    column_width: usize,
+    language: &str,
+) -> io::Result<()> {
+    let Some(side) = side else {
+        write!(
+            stdout,
+            "{}",
+            " ".repeat(LINE_NUMBER_WIDTH + 3 + column_width)
+        )?;
+        return Ok(());
+    };
+
+    let (marker, color) = match side.tag {
+        ChangeTag::Delete => ('-', Color::Red),
+        ChangeTag::Insert => ('+', Color::Green),
+        ChangeTag::Equal => (' ', Color::White),
+    };
+    stdout.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+    write!(
+        stdout,
+        "{:>width$}",
+        side.line_number,
+        width = LINE_NUMBER_WIDTH
+    )?;
+    stdout.set_color(ColorSpec::new().set_fg(Some(color)).set_bold(true))?;
+    write!(stdout, " {} ", marker)?;
+    stdout.reset()?;
+
+    let code: String = side.code.chars().take(column_width).collect();
+    let language = if get_config().use_colors {
+        language
+    } else {
+        "txt"
+    };
+    if highlight_code(&code, language, stdout).is_err() {
+        write!(stdout, "{}", code)?;
+    }
+    stdout.reset()?;
+    write!(
+        stdout,
+        "{}",
+        " ".repeat(column_width - code.chars().count())
+    )?;
+    Ok(())
+}
+
+fn side_by_side_column_width() -> usize {
+    let terminal_width = crossterm::terminal::size()
+        .map(|(columns, _)| columns as usize)
+        .unwrap_or(DEFAULT_TERMINAL_WIDTH);
+    // Each side has a line number and marker, and the sides are split by " ┃ "
+    let chrome = 2 * (LINE_NUMBER_WIDTH + 3) + 3;
+    (terminal_width.saturating_sub(chrome) / 2).max(10)
+}
+
+/// Renders a Markdown file through `see` itself and returns the plain text,
+/// so that the comparison reflects what the reader actually sees. The user's
+/// `--config` is passed on, before the options below that override it.
+fn render_to_text(path: &Path) -> io::Result<String> {
+    let output = Command::new(env::current_exe()?)
+        .args(
+            env::args()
+                .skip(1)
+                .filter(|arg| arg.starts_with("--config=")),
+        )
+        .args(["--diff=false", "--watch=false", "--follow=false"])
+        .arg("--force-render")
+        .arg("--use-colors=false")
+        .arg("--render-images=false")
+        .arg("--show-filename=false")
+        .arg(path)
+        .output()?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(io::Error::other(format!(
+            "Failed to render {}: {}",
+            path.display(),
+            if message.is_empty() {
+                output.status.to_string()
+            } else {
+                message
+            }
+        )));
+    }
+    Ok(strip_ansi(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Removes CSI and OSC escape sequences from rendered output.
+fn strip_ansi(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1B' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1B' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    result
+}
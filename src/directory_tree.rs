@@ -3,9 +3,11 @@ use std::fs;
 use std::path::Path;
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
-const DIRECTORY_ICON: char = '';
-const TREE_BRANCH: &str = "├── ";
-const TREE_LAST_BRANCH: &str = "└── ";
+pub const DIRECTORY_ICON: char = '';
+pub const TREE_BRANCH: &str = "├── ";
+pub const TREE_LAST_BRANCH: &str = "└── ";
+pub const TREE_VERTICAL: &str = "│   ";
+pub const TREE_INDENT: &str = "    ";
 
 pub fn handle_directory(path: &Path) -> std::io::Result<()> {
     print_root_directory(path)?;
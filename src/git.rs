@@ -0,0 +1,120 @@
+use git2::{DiffOptions, ErrorCode, Patch, Repository};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use termcolor::Color;
+
+use crate::compression::Compression;
+use crate::config::get_config;
+use crate::utils::decode_text;
+
+/// Files larger than this are streamed, so they are not compared with HEAD.
+const MAX_COMPARED_FILE_SIZE: u64 = 8 * 1024 * 1024;
+
+/// How a line of the working copy differs from the version in HEAD.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineChange {
+    Added,
+    Modified,
+    /// Lines were deleted just above this one.
+    RemovedAbove,
+    /// Lines were deleted just below this one.
+    RemovedBelow,
+}
+
+impl LineChange {
+    /// The gutter marker, shown in place of the line number separator.
+    pub fn marker(self) -> (char, Color) {
+        match self {
+            LineChange::Added => ('+', Color::Green),
+            LineChange::Modified => ('~', Color::Yellow),
+            LineChange::RemovedAbove => ('‾', Color::Red),
+            LineChange::RemovedBelow => ('_', Color::Red),
+        }
+    }
+}
+
+/// Changes by line number, counted from 1.
+pub type LineChanges = HashMap<usize, LineChange>;
+
+/// Compares a file with its version in the HEAD commit of the git work tree
+/// it is in. Both versions are decoded like the viewer decodes the file, so
+/// the lines match the displayed ones. Files that are not in HEAD yet, or in
+/// a repository without commits, are all added lines. Returns `None` for
+/// files outside a repository, ignored and compressed files, and when
+/// `show_git_changes` is off.
+pub fn line_changes(path: &Path) -> Option<LineChanges> {
+    let config = get_config();
+    if !config.show_git_changes || config.follow {
+        return None;
+    }
+    if fs::metadata(path).ok()?.len() > MAX_COMPARED_FILE_SIZE
+        || Compression::detect(path).is_some()
+    {
+        return None;
+    }
+
+    let path = fs::canonicalize(path).ok()?;
+    let repository = Repository::discover(path.parent()?).ok()?;
+    let workdir = fs::canonicalize(repository.workdir()?).ok()?;
+    let relative_path = path.strip_prefix(&workdir).ok()?;
+
+    // A repository without commits has an unborn HEAD, which is empty
+    let tree = match repository.head() {
+        Ok(head) => Some(head.peel_to_tree().ok()?),
+        Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+        Err(_) => return None,
+    };
+    let (content, encoding) = decode_text(&fs::read(&path).ok()?);
+    let Some(entry) = tree
+        .as_ref()
+        .and_then(|tree| tree.get_path(relative_path).ok())
+    else {
+        if repository.is_path_ignored(relative_path).unwrap_or(true) {
+            return None;
+        }
+        return Some(
+            (1..=content.lines().count())
+                .map(|line| (line, LineChange::Added))
+                .collect(),
+        );
+    };
+    let blob = repository.find_blob(entry.id()).ok()?;
+    let (head_content, _) = encoding.decode_with_bom_removal(blob.content());
+
+    let mut options = DiffOptions::new();
+    options.context_lines(0);
+    let patch = Patch::from_buffers(
+        head_content.as_bytes(),
+        Some(relative_path),
+        content.as_bytes(),
+        Some(relative_path),
+        Some(&mut options),
+    )
+    .ok()?;
+
+    let mut changes = LineChanges::new();
+    for index in 0..patch.num_hunks() {
+        let (hunk, _) = patch.hunk(index).ok()?;
+        let start = hunk.new_start() as usize;
+        let count = hunk.new_lines() as usize;
+        if count == 0 {
+            // A deletion points at the line before it, or the first line
+            if start == 0 {
+                changes.insert(1, LineChange::RemovedAbove);
+            } else {
+                changes.insert(start, LineChange::RemovedBelow);
+            }
+            continue;
+        }
+        let change = if hunk.old_lines() == 0 {
+            LineChange::Added
+        } else {
+            LineChange::Modified
+        };
+        for line in start..start + count {
+            changes.insert(line, change);
+        }
+    }
+    Some(changes)
+}
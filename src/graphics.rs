@@ -0,0 +1,284 @@
+use base64::{engine::general_purpose, Engine as _};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, ImageReader, RgbaImage};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::config::get_config;
+use crate::utils::{CELL_HEIGHT_PX, CELL_WIDTH_PX};
+
+/// Kitty takes image data in chunks of at most this many base64 bytes.
+const KITTY_CHUNK_LEN: usize = 4096;
+
+/// Characters from light to dense for the ASCII renderer.
+const ASCII_RAMP: &[u8] = b" .:-=+*#%@";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphicsProtocol {
+    /// Let viuer pick between Kitty, iTerm and half blocks.
+    Auto,
+    Kitty,
+    Iterm,
+    Sixel,
+    Halfblocks,
+    Ascii,
+    /// Print a placeholder instead of the image.
+    None,
+}
+
+impl GraphicsProtocol {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "kitty" => Some(Self::Kitty),
+            "iterm" | "iterm2" => Some(Self::Iterm),
+            "sixel" => Some(Self::Sixel),
+            "halfblocks" | "blocks" => Some(Self::Halfblocks),
+            "ascii" => Some(Self::Ascii),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    /// The protocol chosen with `--graphics-protocol`, `auto` by default.
+    pub fn configured() -> Self {
+        get_config()
+            .graphics_protocol
+            .as_deref()
+            .and_then(Self::from_name)
+            .unwrap_or(Self::Auto)
+    }
+}
+
+/// Prints an image file with the configured graphics protocol, returning the
+/// number of columns and rows it takes up.
+pub fn print_image(path: &Path, viuer_config: &viuer::Config) -> io::Result<(u32, u32)> {
+    let protocol = GraphicsProtocol::configured();
+    match protocol {
+        GraphicsProtocol::Auto => {
+            viuer::print_from_file(path, viuer_config).map_err(io::Error::other)
+        }
+        GraphicsProtocol::Halfblocks => {
+            let block_config = viuer::Config {
+                use_kitty: false,
+                use_iterm: false,
+                ..*viuer_config
+            };
+            viuer::print_from_file(path, &block_config).map_err(io::Error::other)
+        }
+        GraphicsProtocol::None => Ok((0, 0)),
+        GraphicsProtocol::Kitty | GraphicsProtocol::Iterm | GraphicsProtocol::Sixel => {
+            let image = open_image(path)?;
+            let (columns, rows) = fit_cells(&image, viuer_config);
+            let sequence = match protocol {
+                GraphicsProtocol::Kitty => kitty_sequence(&image, columns, rows)?,
+                GraphicsProtocol::Iterm => iterm_sequence(&image, columns, rows)?,
+                _ => sixel_sequence(&image, columns, rows),
+            };
+            let mut stdout = io::stdout();
+            writeln!(stdout, "{}", passthrough(&sequence))?;
+            stdout.flush()?;
+            Ok((columns, rows))
+        }
+        GraphicsProtocol::Ascii => {
+            let image = open_image(path)?;
+            let (columns, rows) = fit_cells(&image, viuer_config);
+            print_ascii(&image, columns, rows)?;
+            Ok((columns, rows))
+        }
+    }
+}
+
+/// Decodes an image by its content, since temporary files have no extension.
+fn open_image(path: &Path) -> io::Result<DynamicImage> {
+    ImageReader::open(path)?
+        .with_guessed_format()?
+        .decode()
+        .map_err(io::Error::other)
+}
+
+/// Fits an image into terminal cells the way viuer does: within the
+/// configured width and height, or the terminal, keeping the aspect ratio
+/// unless both are given. A cell is twice as tall as it is wide.
+fn fit_cells(image: &DynamicImage, viuer_config: &viuer::Config) -> (u32, u32) {
+    let (width, height) = (image.width().max(1), image.height().max(1));
+    match (viuer_config.width, viuer_config.height) {
+        (Some(columns), Some(rows)) => (columns, rows),
+        (Some(columns), None) => fit_dimensions(width, height, columns, height),
+        (None, Some(rows)) => fit_dimensions(width, height, width, rows),
+        (None, None) => {
+            let (columns, rows) = crossterm::terminal::size().unwrap_or((80, 24));
+            let (columns, fitted_rows) =
+                fit_dimensions(width, height, u32::from(columns), u32::from(rows));
+            // Leave a line for the prompt
+            (
+                columns,
+                fitted_rows.min(u32::from(rows).saturating_sub(1).max(1)),
+            )
+        }
+    }
+}
+
+fn fit_dimensions(width: u32, height: u32, max_columns: u32, max_rows: u32) -> (u32, u32) {
+    let max_height = max_rows * 2;
+    if width <= max_columns && height <= max_height {
+        return (width, height.div_ceil(2).max(1));
+    }
+    if u64::from(max_columns) * u64::from(height) <= u64::from(width) * u64::from(max_height) {
+        let rows = u64::from(height) * u64::from(max_columns) / u64::from(width) / 2;
+        (max_columns, (rows as u32).max(1))
+    } else {
+        let columns = u64::from(width) * u64::from(max_height) / u64::from(height);
+        ((columns as u32).max(1), max_rows.max(1))
+    }
+}
+
+fn encode_png(image: &DynamicImage) -> io::Result<Vec<u8>> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut io::Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(io::Error::other)?;
+    Ok(png)
+}
+
+/// Sends the image as PNG data, split into the chunks Kitty expects.
+fn kitty_sequence(image: &DynamicImage, columns: u32, rows: u32) -> io::Result<String> {
+    let encoded = general_purpose::STANDARD.encode(encode_png(image)?);
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(KITTY_CHUNK_LEN).collect();
+
+    let mut sequence = String::new();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(index + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+        if index == 0 {
+            let _ = write!(
+                sequence,
+                "\x1b_Gf=100,a=T,t=d,c={},r={},m={};{}\x1b\\",
+                columns, rows, more, chunk
+            );
+        } else {
+            let _ = write!(sequence, "\x1b_Gm={};{}\x1b\\", more, chunk);
+        }
+    }
+    Ok(sequence)
+}
+
+fn iterm_sequence(image: &DynamicImage, columns: u32, rows: u32) -> io::Result<String> {
+    let png = encode_png(image)?;
+    Ok(format!(
+        "\x1b]1337;File=inline=1;preserveAspectRatio=1;size={};width={};height={}:{}\x07",
+        png.len(),
+        columns,
+        rows,
+        general_purpose::STANDARD.encode(&png)
+    ))
+}
+
+/// Encodes the image as Sixel graphics with a 6×6×6 colour cube, leaving
+/// transparent pixels undrawn.
+fn sixel_sequence(image: &DynamicImage, columns: u32, rows: u32) -> String {
+    let image = image
+        .resize(
+            columns * CELL_WIDTH_PX,
+            rows * CELL_HEIGHT_PX,
+            FilterType::Triangle,
+        )
+        .to_rgba8();
+    let (width, height) = image.dimensions();
+
+    let mut sequence = format!("\x1bP0;1;0q\"1;1;{};{}", width, height);
+    for index in 0..216u32 {
+        let (red, green, blue) = (index / 36, index / 6 % 6, index % 6);
+        let _ = write!(
+            sequence,
+            "#{};2;{};{};{}",
+            index,
+            red * 20,
+            green * 20,
+            blue * 20
+        );
+    }
+
+    for band_top in (0..height).step_by(6) {
+        let band_height = (height - band_top).min(6);
+        // For each colour, the sixel bits set in every column of the band
+        let mut colors: BTreeMap<u32, Vec<u8>> = BTreeMap::new();
+        for x in 0..width {
+            for offset in 0..band_height {
+                let pixel = image.get_pixel(x, band_top + offset);
+                if pixel[3] < 128 {
+                    continue;
+                }
+                colors
+                    .entry(cube_index(pixel.0))
+                    .or_insert_with(|| vec![0; width as usize])[x as usize] |= 1 << offset;
+            }
+        }
+        for (index, bits) in colors {
+            let _ = write!(sequence, "#{}", index);
+            push_sixel_runs(&mut sequence, &bits);
+            // Return to the start of the band for the next colour
+            sequence.push('$');
+        }
+        sequence.push('-');
+    }
+
+    sequence.push_str("\x1b\\");
+    sequence
+}
+
+fn cube_index([red, green, blue, _]: [u8; 4]) -> u32 {
+    let level = |channel: u8| (u32::from(channel) * 5 + 127) / 255;
+    level(red) * 36 + level(green) * 6 + level(blue)
+}
+
+/// Appends sixel characters, run-length encoding repeated columns.
+fn push_sixel_runs(sequence: &mut String, bits: &[u8]) {
+    let mut columns = bits.iter().peekable();
+    while let Some(&value) = columns.next() {
+        let mut run = 1;
+        while columns.next_if_eq(&&value).is_some() {
+            run += 1;
+        }
+        let character = char::from(63 + value);
+        if run > 3 {
+            let _ = write!(sequence, "!{}{}", run, character);
+        } else {
+            sequence.extend(std::iter::repeat_n(character, run));
+        }
+    }
+}
+
+/// Draws the image with plain ASCII characters, for terminals that can show
+/// neither graphics nor colours.
+fn print_ascii(image: &DynamicImage, columns: u32, rows: u32) -> io::Result<()> {
+    let image: RgbaImage = image
+        .resize_exact(columns, rows, FilterType::Triangle)
+        .to_rgba8();
+    let mut stdout = io::stdout();
+    for y in 0..rows {
+        let line: String = (0..columns)
+            .map(|x| {
+                let [red, green, blue, alpha] = image.get_pixel(x, y).0;
+                let luma = (u32::from(red) * 299 + u32::from(green) * 587 + u32::from(blue) * 114)
+                    / 1000
+                    * u32::from(alpha)
+                    / 255;
+                let index = luma as usize * (ASCII_RAMP.len() - 1) / 255;
+                char::from(ASCII_RAMP[index])
+            })
+            .collect();
+        writeln!(stdout, "{}", line.trim_end())?;
+    }
+    stdout.flush()
+}
+
+/// Wraps an escape sequence so tmux forwards it to the outer terminal.
+fn passthrough(sequence: &str) -> String {
+    if std::env::var_os("TMUX").is_none() {
+        return sequence.to_string();
+    }
+    format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+}
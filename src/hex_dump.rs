@@ -0,0 +1,128 @@
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+use crate::config::get_config;
+
+const BYTES_PER_LINE: usize = 16;
+const BYTES_PER_GROUP: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ByteCategory {
+    Null,
+    Printable,
+    Whitespace,
+    Control,
+    High,
+}
+
+impl ByteCategory {
+    fn of(byte: u8) -> Self {
+        match byte {
+            0x00 => ByteCategory::Null,
+            b'\t' | b'\n' | b'\r' | b' ' | 0x0b | 0x0c => ByteCategory::Whitespace,
+            0x21..=0x7e => ByteCategory::Printable,
+            0x01..=0x1f | 0x7f => ByteCategory::Control,
+            _ => ByteCategory::High,
+        }
+    }
+
+    fn color_spec(&self) -> ColorSpec {
+        let mut spec = ColorSpec::new();
+        match self {
+            ByteCategory::Null => spec.set_fg(Some(Color::White)).set_dimmed(true),
+            ByteCategory::Printable => spec.set_fg(Some(Color::Cyan)),
+            ByteCategory::Whitespace => spec.set_fg(Some(Color::Green)),
+            ByteCategory::Control => spec.set_fg(Some(Color::Magenta)),
+            ByteCategory::High => spec.set_fg(Some(Color::Yellow)),
+        };
+        spec
+    }
+
+    fn ascii_char(&self, byte: u8) -> char {
+        match self {
+            ByteCategory::Null => '⋄',
+            ByteCategory::Printable => byte as char,
+            ByteCategory::Whitespace if byte == b' ' => ' ',
+            ByteCategory::Whitespace => '_',
+            ByteCategory::Control => '•',
+            ByteCategory::High => '×',
+        }
+    }
+}
+
+/// Prints an offset / hex / ASCII dump of `source`, honouring the configured
+/// `--offset` and `--length` range. Only that range is read, a line at a time.
+pub fn render_hex_dump<R: Read + Seek>(mut source: R) -> io::Result<()> {
+    let config = get_config();
+    let start = config.hex_offset.unwrap_or(0);
+    source.seek(SeekFrom::Start(start))?;
+    let mut reader = BufReader::new(source).take(config.hex_length.unwrap_or(u64::MAX));
+
+    let color_choice = if config.use_colors {
+        ColorChoice::Always
+    } else {
+        ColorChoice::Never
+    };
+    let mut stdout = StandardStream::stdout(color_choice);
+
+    let mut offset = start as usize;
+    let mut line = [0; BYTES_PER_LINE];
+    loop {
+        let count = read_line(&mut reader, &mut line)?;
+        if count == 0 {
+            break;
+        }
+        render_hex_line(&mut stdout, offset, &line[..count])?;
+        offset += count;
+    }
+
+    Ok(())
+}
+
+/// Fills `line` as far as the input allows, which only falls short at its end.
+fn read_line<R: Read>(reader: &mut R, line: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < line.len() {
+        match reader.read(&mut line[filled..]) {
+            Ok(0) => break,
+            Ok(count) => filled += count,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn render_hex_line(stdout: &mut StandardStream, offset: usize, chunk: &[u8]) -> io::Result<()> {
+    stdout.set_color(ColorSpec::new().set_fg(Some(Color::Blue)))?;
+    write!(stdout, "{:08x}", offset)?;
+    stdout.reset()?;
+    write!(stdout, "  ")?;
+
+    for i in 0..BYTES_PER_LINE {
+        if i > 0 && i % BYTES_PER_GROUP == 0 {
+            write!(stdout, " ")?;
+        }
+        match chunk.get(i) {
+            Some(&byte) => {
+                stdout.set_color(&ByteCategory::of(byte).color_spec())?;
+                write!(stdout, "{:02x}", byte)?;
+                stdout.reset()?;
+                write!(stdout, " ")?;
+            }
+            None => write!(stdout, "   ")?,
+        }
+    }
+
+    write!(stdout, " │")?;
+    for &byte in chunk {
+        let category = ByteCategory::of(byte);
+        stdout.set_color(&category.color_spec())?;
+        write!(stdout, "{}", category.ascii_char(byte))?;
+    }
+    stdout.reset()?;
+    let padding = BYTES_PER_LINE - chunk.len();
+    writeln!(stdout, "{}│", " ".repeat(padding))?;
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use htmd::HtmlToMarkdown;
+use std::io;
+
+use crate::app;
+use crate::render::render_markdown;
+
+/// Tags whose content never makes sense as rendered text.
+const SKIPPED_TAGS: [&str; 6] = ["script", "style", "noscript", "template", "head", "nav"];
+
+/// Converts a full HTML document to Markdown and renders it, leading with the
+/// document `<title>` as a heading.
+pub fn render_html_document(content: &str) -> io::Result<()> {
+    let title = extract_element(content, "title").map(|title| title.trim().to_string());
+    let body = extract_element(content, "main")
+        .or_else(|| extract_element(content, "article"))
+        .or_else(|| extract_element(content, "body"))
+        .unwrap_or(content);
+
+    let converter = HtmlToMarkdown::builder()
+        .skip_tags(SKIPPED_TAGS.to_vec())
+        .build();
+    let mut markdown = converter.convert(body)?;
+
+    if let Some(title) = title.filter(|title| !title.is_empty()) {
+        if !markdown.trim_start().starts_with(&format!("# {}", title)) {
+            markdown = format!("# {}\n\n{}", title, markdown);
+        }
+    }
+
+    let json = app::parse_and_process_markdown(&markdown)?;
+    render_markdown(&json)
+}
+
+/// Returns the inner HTML of the first `<tag>` element, matching the tag name
+/// case-insensitively.
+fn extract_element<'a>(html: &'a str, tag: &str) -> Option<&'a str> {
+    // ASCII lowercasing keeps byte offsets valid for slicing the original
+    let lowercase = html.to_ascii_lowercase();
+    let open = format!("<{}", tag);
+    let mut search_from = 0;
+
+    loop {
+        let start = search_from + lowercase[search_from..].find(&open)?;
+        let after_name = lowercase.as_bytes().get(start + open.len())?;
+        // Skip tags that merely share a prefix, like <main-menu> for <main>
+        if !matches!(after_name, b'>' | b' ' | b'\t' | b'\n' | b'\r' | b'/') {
+            search_from = start + open.len();
+            continue;
+        }
+        let content_start = start + lowercase[start..].find('>')? + 1;
+        let content_end = content_start + lowercase[content_start..].find(&format!("</{}", tag))?;
+        return Some(&html[content_start..content_end]);
+    }
+}
@@ -0,0 +1,163 @@
+use exif::{Exif, In, Tag, Value};
+use image::{ColorType, ImageDecoder, ImageReader};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use termcolor::{Color, ColorSpec, WriteColor};
+
+use crate::render::get_stdout;
+use crate::utils::{format_size, is_svg, read_exif};
+
+/// Prints the dimensions, format, colour type, file size and EXIF data of an
+/// image below it. Fields that cannot be read are left out.
+pub fn print_image_info(path: &Path) -> io::Result<()> {
+    let mut rows: Vec<(&str, String)> = Vec::new();
+
+    if is_svg(path) {
+        rows.push(("Format", "SVG".to_string()));
+    } else if let Ok(reader) = ImageReader::open(path).and_then(|r| r.with_guessed_format()) {
+        if let Some(format) = reader.format() {
+            rows.push(("Format", format!("{:?}", format).to_uppercase()));
+        }
+        if let Ok(decoder) = reader.into_decoder() {
+            let (width, height) = decoder.dimensions();
+            rows.push(("Dimensions", format!("{} × {} px", width, height)));
+            rows.push(("Colour", describe_color_type(decoder.color_type())));
+        }
+    }
+
+    if let Ok(metadata) = fs::metadata(path) {
+        rows.push(("File size", format_size(metadata.len())));
+    }
+
+    if let Some(exif) = read_exif(path) {
+        exif_rows(&exif, &mut rows);
+    }
+
+    let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    let mut stdout = get_stdout();
+    for (label, value) in rows {
+        stdout.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+        write!(stdout, "{:<width$}", label, width = label_width)?;
+        stdout.reset()?;
+        writeln!(stdout, "  {}", value)?;
+    }
+    Ok(())
+}
+
+fn exif_rows(exif: &Exif, rows: &mut Vec<(&str, String)>) {
+    let make = ascii_field(exif, Tag::Make);
+    let model = ascii_field(exif, Tag::Model);
+    let camera = match (make, model) {
+        // Most cameras repeat the make in the model name
+        (Some(make), Some(model)) if model.starts_with(&make) => Some(model),
+        (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+        (make, model) => make.or(model),
+    };
+    if let Some(camera) = camera {
+        rows.push(("Camera", camera));
+    }
+    if let Some(lens) = ascii_field(exif, Tag::LensModel) {
+        rows.push(("Lens", lens));
+    }
+
+    if let Some(taken) =
+        ascii_field(exif, Tag::DateTimeOriginal).or_else(|| ascii_field(exif, Tag::DateTime))
+    {
+        // EXIF writes dates as `YYYY:MM:DD HH:MM:SS`
+        rows.push(("Taken", taken.replacen(':', "-", 2)));
+    }
+
+    let exposure: Vec<String> = [
+        Tag::ExposureTime,
+        Tag::FNumber,
+        Tag::PhotographicSensitivity,
+        Tag::FocalLength,
+    ]
+    .into_iter()
+    .filter_map(|tag| {
+        let field = exif.get_field(tag, In::PRIMARY)?;
+        let value = field.display_value().with_unit(exif).to_string();
+        Some(if tag == Tag::PhotographicSensitivity {
+            format!("ISO {}", value)
+        } else {
+            value
+        })
+    })
+    .collect();
+    if !exposure.is_empty() {
+        rows.push(("Exposure", exposure.join(", ")));
+    }
+
+    if let Some(orientation) = exif
+        .get_field(Tag::Orientation, In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+    {
+        rows.push(("Orientation", describe_orientation(orientation).to_string()));
+    }
+
+    let latitude = coordinate(exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, "S");
+    let longitude = coordinate(exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, "W");
+    if let (Some(latitude), Some(longitude)) = (latitude, longitude) {
+        rows.push(("GPS", format!("{:.6}, {:.6}", latitude, longitude)));
+    }
+}
+
+fn ascii_field(exif: &Exif, tag: Tag) -> Option<String> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(values) => {
+            let text = String::from_utf8_lossy(values.first()?).trim().to_string();
+            (!text.is_empty()).then_some(text)
+        }
+        _ => None,
+    }
+}
+
+/// Converts degrees, minutes and seconds to signed decimal degrees, negative
+/// when the reference is `negative_ref` (south or west).
+fn coordinate(exif: &Exif, tag: Tag, ref_tag: Tag, negative_ref: &str) -> Option<f64> {
+    let Value::Rational(parts) = &exif.get_field(tag, In::PRIMARY)?.value else {
+        return None;
+    };
+    let degrees = parts
+        .iter()
+        .zip([1.0, 60.0, 3600.0])
+        .map(|(part, divisor)| part.to_f64() / divisor)
+        .sum::<f64>();
+    if !degrees.is_finite() {
+        return None;
+    }
+    let negative = ascii_field(exif, ref_tag).is_some_and(|r| r == negative_ref);
+    Some(if negative { -degrees } else { degrees })
+}
+
+fn describe_color_type(color_type: ColorType) -> String {
+    let description = match color_type {
+        ColorType::L8 => "Greyscale, 8-bit",
+        ColorType::La8 => "Greyscale with alpha, 8-bit",
+        ColorType::Rgb8 => "RGB, 8-bit",
+        ColorType::Rgba8 => "RGBA, 8-bit",
+        ColorType::L16 => "Greyscale, 16-bit",
+        ColorType::La16 => "Greyscale with alpha, 16-bit",
+        ColorType::Rgb16 => "RGB, 16-bit",
+        ColorType::Rgba16 => "RGBA, 16-bit",
+        ColorType::Rgb32F => "RGB, 32-bit float",
+        ColorType::Rgba32F => "RGBA, 32-bit float",
+        other => return format!("{:?}", other),
+    };
+    description.to_string()
+}
+
+fn describe_orientation(orientation: u32) -> &'static str {
+    match orientation {
+        1 => "Normal",
+        2 => "Mirrored horizontally",
+        3 => "Rotated 180°",
+        4 => "Mirrored vertically",
+        5 => "Mirrored horizontally, rotated 90° counter-clockwise",
+        6 => "Rotated 90° clockwise",
+        7 => "Mirrored horizontally, rotated 90° clockwise",
+        8 => "Rotated 90° counter-clockwise",
+        _ => "Unknown",
+    }
+}
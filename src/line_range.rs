@@ -0,0 +1,144 @@
+use lazy_static::lazy_static;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::config::get_config;
+
+lazy_static! {
+    /// The range given with the file name, as in `main.rs:40:80`.
+    static ref FILE_RANGE: Mutex<Option<LineRange>> = Mutex::new(None);
+}
+
+/// Lines `start` to `end`, counted from 1 and inclusive. Without an end the
+/// range runs to the end of the file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+impl LineRange {
+    /// Parses `40:80`, `40:`, `:80` or a single line like `55`. An end before
+    /// the start is read as a column, as in compiler output like
+    /// `main.rs:55:12`, so only the line is kept.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let (start, end) = match spec.split_once(':') {
+            Some((start, end)) => (start, Some(end)),
+            None => (spec, None),
+        };
+        let start = match start {
+            "" => 1,
+            start => start.parse().ok().filter(|&start| start > 0)?,
+        };
+        let end = match end {
+            None => Some(start),
+            Some("") => None,
+            Some(end) => {
+                let end: usize = end.parse().ok()?;
+                Some(if end < start { start } else { end })
+            }
+        };
+        Some(LineRange { start, end })
+    }
+
+    pub fn contains(&self, line: usize) -> bool {
+        line >= self.start && self.end.is_none_or(|end| line <= end)
+    }
+
+    fn is_single_line(&self) -> bool {
+        self.end == Some(self.start)
+    }
+
+    fn widen(self, context: usize) -> Self {
+        LineRange {
+            start: self.start.saturating_sub(context).max(1),
+            end: self.end.map(|end| end + context),
+        }
+    }
+}
+
+/// Splits `main.rs:40:80` or `main.rs:55` into the file and the line range.
+/// The file may also be an archive member, as in `release.zip:src/main.rs:12`.
+pub fn split_line_range(path: &Path) -> Option<(PathBuf, LineRange)> {
+    let path = path.to_str()?;
+    let (rest, last) = path.rsplit_once(':')?;
+    if last.is_empty() || !last.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    match rest.rsplit_once(':') {
+        Some((file, first)) if !first.is_empty() && first.bytes().all(|b| b.is_ascii_digit()) => {
+            Some((
+                PathBuf::from(file),
+                LineRange::parse(&format!("{}:{}", first, last))?,
+            ))
+        }
+        _ => Some((PathBuf::from(rest), LineRange::parse(last)?)),
+    }
+}
+
+/// Sets the range given with the file currently shown, or clears it.
+pub fn set_file_range(range: Option<LineRange>) {
+    *FILE_RANGE.lock().unwrap() = range;
+}
+
+/// The lines to show and highlight, from the file name, `--line-range`,
+/// `--highlight-line` and `--context`.
+pub struct LineSelection {
+    range: Option<LineRange>,
+    highlighted: Vec<usize>,
+}
+
+impl LineSelection {
+    pub fn current() -> Self {
+        let config = get_config();
+        let range = FILE_RANGE
+            .lock()
+            .unwrap()
+            .or_else(|| config.line_range.as_deref().and_then(LineRange::parse));
+        let mut highlighted = config.highlight_lines.clone();
+        let context = config.context.unwrap_or(0);
+
+        let range = match range {
+            Some(range) => {
+                // A single line shown with context is the line of interest
+                if range.is_single_line() && context > 0 && highlighted.is_empty() {
+                    highlighted.push(range.start);
+                }
+                Some(range.widen(context))
+            }
+            None if context > 0 && !highlighted.is_empty() => Some(
+                LineRange {
+                    start: *highlighted.iter().min().unwrap(),
+                    end: highlighted.iter().max().copied(),
+                }
+                .widen(context),
+            ),
+            None => None,
+        };
+        LineSelection { range, highlighted }
+    }
+
+    /// Whether any lines were picked out, in which case documents are shown
+    /// as source code.
+    pub fn is_active() -> bool {
+        let config = get_config();
+        FILE_RANGE.lock().unwrap().is_some()
+            || config.line_range.is_some()
+            || !config.highlight_lines.is_empty()
+    }
+
+    pub fn shows(&self, line: usize) -> bool {
+        self.range.is_none_or(|range| range.contains(line))
+    }
+
+    /// Whether every line still to come is past the range.
+    pub fn is_past(&self, line: usize) -> bool {
+        self.range
+            .and_then(|range| range.end)
+            .is_some_and(|end| line > end)
+    }
+
+    pub fn highlights(&self, line: usize) -> bool {
+        self.highlighted.contains(&line)
+    }
+}
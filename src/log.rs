@@ -0,0 +1,415 @@
+use serde_json::{Map, Value};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use termcolor::{Color, ColorSpec, WriteColor};
+
+use crate::config::get_config;
+use crate::diff::looks_like_diff;
+use crate::render::get_stdout;
+use crate::stream::open_text_stream;
+
+const TIMESTAMP_KEYS: [&str; 5] = ["ts", "time", "timestamp", "@timestamp", "t"];
+const LEVEL_KEYS: [&str; 5] = ["level", "lvl", "severity", "loglevel", "log.level"];
+const MESSAGE_KEYS: [&str; 3] = ["msg", "message", "event"];
+/// Lines read from standard input before deciding whether it is a log.
+pub const SNIFFED_LINES: usize = 3;
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl Level {
+    pub fn from_str(s: &str) -> Option<Self> {
+        let token = s.trim_matches(|c: char| !c.is_ascii_alphabetic());
+        match token.to_uppercase().as_str() {
+            "TRACE" | "TRC" => Some(Level::Trace),
+            "DEBUG" | "DBG" => Some(Level::Debug),
+            "INFO" | "INF" | "NOTICE" => Some(Level::Info),
+            "WARN" | "WARNING" | "WRN" => Some(Level::Warn),
+            "ERROR" | "ERR" => Some(Level::Error),
+            "FATAL" | "CRIT" | "CRITICAL" | "PANIC" | "EMERG" | "ALERT" => Some(Level::Fatal),
+            _ => None,
+        }
+    }
+
+    fn color_spec(&self) -> ColorSpec {
+        let mut spec = ColorSpec::new();
+        match self {
+            Level::Trace => spec.set_fg(Some(Color::White)).set_dimmed(true),
+            Level::Debug => spec.set_fg(Some(Color::Blue)),
+            Level::Info => spec.set_fg(Some(Color::Green)),
+            Level::Warn => spec.set_fg(Some(Color::Yellow)).set_bold(true),
+            Level::Error => spec.set_fg(Some(Color::Red)).set_bold(true),
+            Level::Fatal => spec.set_fg(Some(Color::Magenta)).set_bold(true),
+        };
+        spec
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Level::Trace => "TRACE",
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO ",
+            Level::Warn => "WARN ",
+            Level::Error => "ERROR",
+            Level::Fatal => "FATAL",
+        }
+    }
+}
+
+/// A log line split into its recognised parts.
+struct Record {
+    timestamp: Option<String>,
+    level: Option<Level>,
+    message: String,
+    fields: Vec<(String, Value)>,
+}
+
+/// Keeps track of whether the previous record passed the `--level` filter,
+/// so continuation lines such as stack traces follow their record.
+pub struct LogRenderer {
+    min_level: Option<Level>,
+    showing: bool,
+    stdout: Box<dyn WriteColor>,
+}
+
+impl LogRenderer {
+    pub fn new() -> Self {
+        LogRenderer {
+            min_level: get_config().log_level.as_deref().and_then(Level::from_str),
+            showing: true,
+            stdout: get_stdout(),
+        }
+    }
+
+    pub fn render_line(&mut self, line: &str) -> io::Result<()> {
+        let record = parse_line(line);
+        if let (Some(min_level), Some(level)) = (self.min_level, record.level) {
+            self.showing = level >= min_level;
+        }
+        if !self.showing {
+            return Ok(());
+        }
+        self.write_record(&record)?;
+        self.stdout.flush()
+    }
+
+    fn write_record(&mut self, record: &Record) -> io::Result<()> {
+        let stdout = &mut self.stdout;
+
+        if let Some(timestamp) = &record.timestamp {
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::White)).set_dimmed(true))?;
+            write!(stdout, "{} ", timestamp)?;
+            stdout.reset()?;
+        }
+        if let Some(level) = record.level {
+            stdout.set_color(&level.color_spec())?;
+            write!(stdout, "{} ", level.label())?;
+            stdout.reset()?;
+        }
+
+        let (message, payload) = split_json_payload(&record.message);
+        if let Some(level) = record.level.filter(|level| *level >= Level::Warn) {
+            stdout.set_color(ColorSpec::new().set_fg(level.color_spec().fg().copied()))?;
+        }
+        write!(stdout, "{}", message)?;
+        stdout.reset()?;
+
+        let mut nested = Vec::new();
+        for (key, value) in &record.fields {
+            if value.is_object() || value.is_array() {
+                nested.push((key, value));
+                continue;
+            }
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+            write!(stdout, " {}", key)?;
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::White)).set_dimmed(true))?;
+            match value {
+                Value::String(s) => write!(stdout, "={}", s)?,
+                other => write!(stdout, "={}", other)?,
+            }
+            stdout.reset()?;
+        }
+        writeln!(stdout)?;
+
+        for (key, value) in nested {
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+            writeln!(stdout, "    {}:", key)?;
+            stdout.reset()?;
+            write_pretty_json(stdout, value)?;
+        }
+        if let Some(payload) = payload {
+            write_pretty_json(stdout, &payload)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn render_log(content: &str) -> io::Result<()> {
+    let mut renderer = LogRenderer::new();
+    for line in content.lines() {
+        renderer.render_line(line)?;
+    }
+    Ok(())
+}
+
+/// Renders log lines as they arrive instead of waiting for the end of input.
+pub fn stream_log<R: BufRead>(head: &str, reader: R) -> io::Result<()> {
+    let mut renderer = LogRenderer::new();
+    for line in head.lines() {
+        renderer.render_line(line)?;
+    }
+    for line in reader.lines() {
+        renderer.render_line(&line?)?;
+    }
+    Ok(())
+}
+
+/// Renders a log file as it is read, which with `--follow` never ends.
+pub fn stream_log_file(path: &Path) -> io::Result<()> {
+    let mut renderer = LogRenderer::new();
+    for line in open_text_stream(path)?.lines() {
+        renderer.render_line(&line?)?;
+    }
+    Ok(())
+}
+
+/// Returns true when the first lines of some input look like they came out
+/// of a logger. One matching line is not enough, and input that reads as a
+/// diff or starts like a Markdown heading, list or link definition is never
+/// taken for a log.
+pub fn looks_like_log(head: &str) -> bool {
+    let lines: Vec<&str> = head
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    if lines.len() < 2 || looks_like_diff(head) {
+        return false;
+    }
+    if lines.iter().any(|line| starts_like_markdown(line)) {
+        return false;
+    }
+    is_log_line(lines[0]) && lines.iter().filter(|line| is_log_line(line)).count() >= 2
+}
+
+fn is_log_line(line: &str) -> bool {
+    if let Ok(Value::Object(object)) = serde_json::from_str::<Value>(line) {
+        return find_key(&object, &LEVEL_KEYS).is_some()
+            || find_key(&object, &MESSAGE_KEYS).is_some();
+    }
+    if is_logfmt_record(&parse_logfmt(line)) {
+        return true;
+    }
+    let (timestamp, rest) = split_timestamp(line);
+    timestamp.is_some() || find_level_token(rest).is_some()
+}
+
+fn starts_like_markdown(line: &str) -> bool {
+    let heading = line.trim_start_matches('#');
+    if heading.len() < line.len() && (heading.is_empty() || heading.starts_with(' ')) {
+        return true;
+    }
+    if ["- ", "* ", "+ ", "> "]
+        .iter()
+        .any(|marker| line.starts_with(marker))
+    {
+        return true;
+    }
+    let ordered = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    if ordered.len() < line.len() && (ordered.starts_with(". ") || ordered.starts_with(") ")) {
+        return true;
+    }
+    // A link reference definition, like `[1]: https://example.com`
+    line.strip_prefix('[')
+        .and_then(|rest| rest.split_once("]:"))
+        .is_some_and(|(label, target)| !label.is_empty() && target.split_whitespace().count() == 1)
+}
+
+fn parse_line(line: &str) -> Record {
+    let trimmed = line.trim();
+
+    if trimmed.starts_with('{') {
+        if let Ok(Value::Object(object)) = serde_json::from_str::<Value>(trimmed) {
+            return record_from_fields(object.into_iter().collect());
+        }
+    }
+
+    let fields = parse_logfmt(trimmed);
+    if is_logfmt_record(&fields) {
+        return record_from_fields(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, Value::String(value)))
+                .collect(),
+        );
+    }
+
+    let (timestamp, rest) = split_timestamp(line);
+    let (level, message) = match find_level_token(rest) {
+        Some((level, before, after)) => (Some(level), format!("{}{}", before, after)),
+        None => (None, rest.to_string()),
+    };
+    Record {
+        timestamp: timestamp.map(str::to_string),
+        level,
+        message,
+        fields: Vec::new(),
+    }
+}
+
+fn is_logfmt_record(fields: &[(String, String)]) -> bool {
+    fields.len() >= 2
+        && fields.iter().any(|(key, _)| {
+            LEVEL_KEYS.contains(&key.as_str()) || MESSAGE_KEYS.contains(&key.as_str())
+        })
+}
+
+fn record_from_fields(fields: Vec<(String, Value)>) -> Record {
+    let mut record = Record {
+        timestamp: None,
+        level: None,
+        message: String::new(),
+        fields: Vec::new(),
+    };
+    for (key, value) in fields {
+        let text = match &value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        if record.timestamp.is_none() && TIMESTAMP_KEYS.contains(&key.as_str()) {
+            record.timestamp = Some(text);
+        } else if record.level.is_none() && LEVEL_KEYS.contains(&key.as_str()) {
+            record.level = Level::from_str(&text);
+            if record.level.is_none() {
+                record.fields.push((key, value));
+            }
+        } else if record.message.is_empty() && MESSAGE_KEYS.contains(&key.as_str()) {
+            record.message = text;
+        } else {
+            record.fields.push((key, value));
+        }
+    }
+    record
+}
+
+fn find_key<'a>(object: &'a Map<String, Value>, keys: &[&str]) -> Option<&'a Value> {
+    keys.iter().find_map(|key| object.get(*key))
+}
+
+/// Parses `key=value key2="quoted value"` pairs; stops at the first token
+/// that is not a pair.
+fn parse_logfmt(line: &str) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let key: String =
+            std::iter::from_fn(|| chars.next_if(|c| *c != '=' && !c.is_whitespace())).collect();
+        if key.is_empty() || chars.next_if_eq(&'=').is_none() {
+            break;
+        }
+        let value = if chars.next_if_eq(&'"').is_some() {
+            let mut value = String::new();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    c => value.push(c),
+                }
+            }
+            value
+        } else {
+            std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace())).collect()
+        };
+        fields.push((key, value));
+    }
+
+    fields
+}
+
+/// Splits a leading ISO 8601, bracketed or syslog timestamp off a line.
+fn split_timestamp(line: &str) -> (Option<&str>, &str) {
+    let bytes = line.as_bytes();
+
+    if bytes.len() >= 10 && bytes[..4].iter().all(u8::is_ascii_digit) && bytes[4] == b'-' {
+        let mut end = line.find(char::is_whitespace).unwrap_or(line.len());
+        // "2024-09-14 12:00:00" keeps the time part together with the date
+        let rest = &line[end..];
+        let time = rest.trim_start();
+        if time.as_bytes().get(2) == Some(&b':')
+            && time.as_bytes()[..2].iter().all(u8::is_ascii_digit)
+        {
+            let offset = end + (rest.len() - time.len());
+            end = offset + time.find(char::is_whitespace).unwrap_or(time.len());
+        }
+        return (Some(&line[..end]), line[end..].trim_start());
+    }
+
+    if line.starts_with('[') && bytes.get(1).is_some_and(u8::is_ascii_digit) {
+        if let Some(end) = line.find(']') {
+            return (Some(&line[..=end]), line[end + 1..].trim_start());
+        }
+    }
+
+    let is_syslog = line.len() >= 15
+        && line.is_char_boundary(15)
+        && MONTHS.iter().any(|month| line.starts_with(month))
+        && bytes[3] == b' '
+        && bytes[9] == b':';
+    if is_syslog {
+        return (Some(&line[..15]), line[15..].trim_start());
+    }
+
+    (None, line)
+}
+
+/// Finds a severity word among the first few tokens of a message, returning
+/// the level and the text around it.
+fn find_level_token(text: &str) -> Option<(Level, &str, &str)> {
+    let mut offset = 0;
+    for token in text.split_inclusive(char::is_whitespace).take(4) {
+        let word = token.trim_end();
+        let bare = word.trim_matches(['[', ']', '(', ')', '<', '>', ':']);
+        let is_marker = word != bare || bare.chars().all(|c| c.is_ascii_uppercase());
+        if is_marker {
+            if let Some(level) = Level::from_str(bare) {
+                return Some((level, &text[..offset], &text[offset + token.len()..]));
+            }
+        }
+        offset += token.len();
+    }
+    None
+}
+
+/// Splits a trailing JSON object off a message so it can be pretty-printed.
+fn split_json_payload(message: &str) -> (&str, Option<Value>) {
+    if let Some(start) = message.find(['{', '[']) {
+        if let Ok(value @ (Value::Object(_) | Value::Array(_))) =
+            serde_json::from_str::<Value>(message[start..].trim_end())
+        {
+            return (message[..start].trim_end(), Some(value));
+        }
+    }
+    (message, None)
+}
+
+fn write_pretty_json(stdout: &mut Box<dyn WriteColor>, value: &Value) -> io::Result<()> {
+    let pretty = serde_json::to_string_pretty(value).unwrap_or_default();
+    stdout.set_color(ColorSpec::new().set_fg(Some(Color::White)).set_dimmed(true))?;
+    for line in pretty.lines() {
+        writeln!(stdout, "    {}", line)?;
+    }
+    stdout.reset()?;
+    Ok(())
+}
@@ -1,20 +1,54 @@
 use crate::config::initialize_app;
-use crate::viewers::{determine_viewer, ViewerManager};
-use std::io::{self, IsTerminal};
+use crate::line_range::LineSelection;
+use crate::utils::{decode_stream, decode_text, detect_encoding, detect_file_encoding, is_svg};
+use crate::viewers::{
+    determine_stdin_viewer, determine_viewer, forced_viewer, reads_raw_file, streams_file,
+    ViewerManager,
+};
+use std::io::{self, BufRead, BufReader, IsTerminal, Read, Write};
 use std::path::Path;
+use url::Url;
 
+mod animation;
 mod app;
+mod archive;
+mod asciidoc;
+mod compression;
 mod config;
 mod constants;
+mod diff;
 mod directory_tree;
+mod git;
+mod graphics;
+mod hex_dump;
+mod html;
+mod image_info;
+mod line_range;
+mod log;
+mod man;
+mod notebook;
+mod org;
+mod remote;
 mod render;
+mod rst;
+mod sqlite;
+mod stream;
 mod utils;
 mod viewers;
+mod watch;
 
 use base64::{engine::general_purpose, Engine as _};
 use std::fs;
 
 fn main() -> std::io::Result<()> {
+    match run() {
+        // Ctrl-C stopped an animation, which has cleaned up after itself
+        Err(e) if e.kind() == io::ErrorKind::Interrupted => std::process::exit(130),
+        result => result,
+    }
+}
+
+fn run() -> std::io::Result<()> {
     let (config, file_paths) = initialize_app()?;
     if config.debug_mode {
         eprintln!("Debug mode enabled");
@@ -22,49 +56,174 @@ fn main() -> std::io::Result<()> {
     }
 
     let viewer_manager = ViewerManager::new();
+    let piped = !io::stdout().is_terminal() && !config.force_render;
+
+    if config.diff_mode {
+        return match file_paths.as_deref() {
+            Some([old_path, new_path]) => diff::render_file_comparison(old_path, new_path),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--diff expects exactly two files",
+            )),
+        };
+    }
+
+    if config.watch && !piped {
+        return match file_paths.as_deref() {
+            Some(paths) if !paths.is_empty() => watch::watch_files(paths),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--watch expects a file",
+            )),
+        };
+    }
 
     match &file_paths {
         Some(paths) if !paths.is_empty() => {
             for path in paths {
-                let path = Path::new(path);
-                if path.is_dir() {
-                    directory_tree::handle_directory(path)?;
-                } else {
-                    let viewer = determine_viewer(path);
-                    if viewer.contains(&"image".to_string()) {
-                        let img_data = fs::read(path)?;
-                        let b64 = general_purpose::STANDARD.encode(&img_data);
-                        let extension = path.extension().unwrap().to_str().unwrap();
-                        let content = format!("data:image/{};base64,{}", extension, b64);
-
-                        if !io::stdout().is_terminal() {
-                            let file_name = path.file_name().unwrap().to_str().unwrap();
-                            println!("![{}]({})", file_name, content);
-                        } else {
-                            viewer_manager.visualize(&viewer, &content, None)?;
-                        }
-                    } else {
-                        let content = app::read_content(Some(path.to_string_lossy().into_owned()))?;
-                        if !io::stdout().is_terminal() {
-                            print!("{}", content);
-                        } else {
-                            viewer_manager.visualize(
-                                &viewer,
-                                &content,
-                                Some(path.to_str().unwrap()),
-                            )?;
-                        }
-                    }
+                match remote::remote_url(path) {
+                    Some(url) => view_url(&url, &viewer_manager, piped)?,
+                    None => view_path(path, &viewer_manager, piped)?,
                 }
             }
         }
         _ => {
-            let content = app::read_content(None)?;
-            if !io::stdout().is_terminal() {
-                print!("{}", content);
+            // Peek at the first lines so log streams can be rendered as they
+            // arrive instead of after the end of input
+            let mut head = Vec::new();
+            for _ in 0..log::SNIFFED_LINES {
+                if io::stdin().lock().read_until(b'\n', &mut head)? == 0 {
+                    break;
+                }
+            }
+            let forced = forced_viewer();
+            let forced_to =
+                |name: &str| matches!(forced.as_deref(), Some([viewer]) if viewer == name);
+            if !piped
+                && (forced_to("log")
+                    || forced.is_none() && log::looks_like_log(&String::from_utf8_lossy(&head)))
+            {
+                return log::stream_log(&String::from_utf8_lossy(&head), io::stdin().lock());
+            }
+
+            // Plain output and code are passed on line by line, so endless
+            // input like `tail -f` shows up as it arrives
+            if piped || forced_to("code") {
+                let encoding = detect_encoding(&head);
+                let input = io::Cursor::new(head).chain(io::stdin().lock());
+                let mut reader = BufReader::new(decode_stream(input, encoding));
+                return if piped {
+                    io::copy(&mut reader, &mut io::stdout().lock()).map(|_| ())
+                } else {
+                    render::stream_code(reader, "txt", stream::STREAMING_LINE_NUMBER_WIDTH, None)
+                };
+            }
+
+            let mut input = head;
+            io::stdin().read_to_end(&mut input)?;
+            let (content, _) = decode_text(&input);
+            viewer_manager.visualize(&determine_stdin_viewer(&content), &content, None)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn view_url(url: &Url, viewer_manager: &ViewerManager, piped: bool) -> io::Result<()> {
+    let document = remote::fetch_document(url)?;
+    // Relative images and links point to the same server
+    render::set_remote_document(Some(document.url.clone()));
+    let result = view_path(&document.path, viewer_manager, piped);
+    render::set_remote_document(None);
+    result
+}
+
+fn view_path(path: &Path, viewer_manager: &ViewerManager, piped: bool) -> io::Result<()> {
+    if !path.exists() {
+        if let Some((archive_path, member)) = archive::split_member_path(path) {
+            let (_temp_dir, member_path) = archive::extract_member(&archive_path, &member)?;
+            return view_path(&member_path, viewer_manager, piped);
+        }
+        if let Some((file_path, range)) =
+            line_range::split_line_range(path).filter(|(file_path, _)| {
+                file_path.exists() || archive::split_member_path(file_path).is_some()
+            })
+        {
+            line_range::set_file_range(Some(range));
+            let result = view_path(&file_path, viewer_manager, piped);
+            line_range::set_file_range(None);
+            return result;
+        }
+    }
+
+    let compression = compression::Compression::detect(path);
+    if path.is_dir() {
+        directory_tree::handle_directory(path)?;
+    } else {
+        let mut viewer = match compression {
+            // Compressed files are shown like the file inside them, which is
+            // read through the decompressor under the original path
+            Some(compression) => {
+                let (_temp_dir, head_path) = compression::decompress_head(path, compression)?;
+                determine_viewer(&head_path)
+            }
+            None => determine_viewer(path),
+        };
+        // Picked out lines are shown in the source of documents
+        if LineSelection::is_active()
+            && forced_viewer().is_none()
+            && !reads_raw_file(&viewer)
+            && !viewer.contains(&"image".to_string())
+        {
+            viewer = vec!["code".to_string()];
+        }
+        if let Some(compression) =
+            compression.filter(|_| reads_raw_file(&viewer) || viewer.contains(&"image".to_string()))
+        {
+            // These viewers read the file themselves
+            let (_temp_dir, inner_path) = compression::decompress(path, compression)?;
+            return view_path(&inner_path, viewer_manager, piped);
+        }
+        if viewer.contains(&"image".to_string()) {
+            let img_data = fs::read(path)?;
+            let b64 = general_purpose::STANDARD.encode(&img_data);
+            // The media type comes from the data, since `--viewer=image` may
+            // be used on files without an image extension
+            let media_type = if is_svg(path) {
+                "image/svg+xml"
+            } else {
+                image::guess_format(&img_data)
+                    .map_err(|_| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("{} is not an image", path.display()),
+                        )
+                    })?
+                    .to_mime_type()
+            };
+            let content = format!("data:{};base64,{}", media_type, b64);
+
+            if piped {
+                let file_name = path.file_name().unwrap().to_str().unwrap();
+                println!("![{}]({})", file_name, content);
+            } else {
+                viewer_manager.visualize(&viewer, &content, None)?;
+            }
+        } else if reads_raw_file(&viewer) {
+            if piped {
+                io::stdout().write_all(&fs::read(path)?)?;
             } else {
-                viewer_manager.visualize(&["markdown".to_string()], &content, None)?;
+                viewer_manager.visualize(&viewer, "", Some(path.to_str().unwrap()))?;
             }
+        } else if piped {
+            stream::copy_text_file(path)?;
+        } else if streams_file(&viewer) {
+            // Detected once, for the filename header and then the stream
+            detect_file_encoding(path)?;
+            viewer_manager.visualize(&viewer, "", Some(path.to_str().unwrap()))?;
+        } else {
+            let content = app::read_content(Some(path.to_string_lossy().into_owned()))?;
+            viewer_manager.visualize(&viewer, &content, Some(path.to_str().unwrap()))?;
         }
     }
 
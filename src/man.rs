@@ -0,0 +1,586 @@
+use serde_json::Value;
+use std::io;
+use std::path::Path;
+
+use crate::app;
+use crate::render::render_markdown;
+use crate::utils::{mdast, read_head};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Font {
+    Roman,
+    Bold,
+    Italic,
+    Mono,
+}
+
+impl Font {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "B" | "BI" | "BD" | "3" => Font::Bold,
+            "I" | "IT" | "2" => Font::Italic,
+            "C" | "CW" | "CR" | "CB" => Font::Mono,
+            _ => Font::Roman,
+        }
+    }
+
+    fn wrap(&self, text: &str) -> Value {
+        match self {
+            Font::Roman => mdast::text(text),
+            Font::Bold => mdast::strong(vec![mdast::text(text)]),
+            Font::Italic => mdast::emphasis(vec![mdast::text(text)]),
+            Font::Mono => mdast::inline_code(text),
+        }
+    }
+}
+
+/// Man pages are picked by a `.roff`/`.man`/`.mdoc` extension, or by a
+/// section extension (`ls.1`, `crontab.5`, `git-config.1p`) on a file that
+/// starts like one, since rotated logs and shared libraries end in digits too.
+pub fn is_man_page(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .unwrap_or("");
+    if matches!(extension, "roff" | "man" | "mdoc") {
+        return true;
+    }
+    let mut chars = extension.chars();
+    let is_section = chars.next().is_some_and(|c| matches!(c, '1'..='9'))
+        && extension.len() <= 3
+        && chars.all(|c| c.is_ascii_lowercase());
+    is_section
+        && read_head(path).is_ok_and(|head| {
+            String::from_utf8_lossy(&head)
+                .lines()
+                .any(|line| line.starts_with(".TH ") || line.starts_with(".Dd ") || line == ".Dd")
+        })
+}
+
+pub fn render_man(content: &str) -> io::Result<()> {
+    let mut json = roff_to_mdast(content);
+    app::process_markdown_ast(&mut json);
+    render_markdown(&json)
+}
+
+/// Converts `man` and `mdoc` roff sources to an mdast tree.
+pub fn roff_to_mdast(content: &str) -> Value {
+    let mut converter = Converter::default();
+    for line in content.lines() {
+        converter.line(line);
+    }
+    converter.finish()
+}
+
+struct Item {
+    term: Vec<Value>,
+    body: Vec<Value>,
+}
+
+#[derive(Default)]
+struct Converter {
+    blocks: Vec<Value>,
+    inline: Vec<Value>,
+    items: Vec<Item>,
+    in_item: bool,
+    expect_tag: bool,
+    pending_heading: Option<usize>,
+    pending_font: Option<Font>,
+    literal: Option<String>,
+    name: Option<String>,
+}
+
+impl Converter {
+    fn line(&mut self, line: &str) {
+        if let Some(rest) = line.strip_prefix('.').or_else(|| line.strip_prefix('\'')) {
+            let rest = rest.trim_start();
+            if rest.starts_with("\\\"") || rest.is_empty() {
+                return;
+            }
+            let (name, args) = rest.split_once([' ', '\t']).unwrap_or((rest, ""));
+            self.request(name, args.trim());
+        } else if let Some(literal) = &mut self.literal {
+            literal.push_str(&plain_text(line));
+            literal.push('\n');
+        } else if line.trim().is_empty() {
+            self.end_paragraph();
+        } else if let Some(depth) = self.pending_heading.take() {
+            self.heading(depth, parse_inline(line, Font::Roman));
+        } else {
+            let font = self.pending_font.take().unwrap_or(Font::Roman);
+            self.push_inline(parse_inline(line, font));
+        }
+    }
+
+    fn request(&mut self, name: &str, args: &str) {
+        match name {
+            "TH" | "Dt" => {
+                let args = split_args(args);
+                let title = match (args.first(), args.get(1)) {
+                    (Some(name), Some(section)) => format!("{}({})", name, section),
+                    (Some(name), None) => name.to_string(),
+                    _ => return,
+                };
+                self.end_list();
+                self.blocks
+                    .push(mdast::heading(1, vec![mdast::text(&title)]));
+            }
+            "SH" | "Sh" => self.section(2, args),
+            "SS" | "Ss" => self.section(3, args),
+            "PP" | "LP" | "P" | "Pp" | "HP" => {
+                self.end_paragraph();
+                if !self.in_item {
+                    self.end_list();
+                }
+            }
+            "br" | "sp" => self.end_paragraph(),
+            "TP" => {
+                self.start_item(Vec::new());
+                self.expect_tag = true;
+            }
+            "IP" => {
+                let tag = split_args(args).into_iter().next().unwrap_or_default();
+                let term = match plain_text(&tag).as_str() {
+                    "" | "•" | "-" | "*" | "o" => Vec::new(),
+                    _ => parse_inline(&tag, Font::Roman),
+                };
+                self.start_item(term);
+            }
+            "RS" | "RE" | "in" | "ad" | "na" | "hy" | "nh" | "ne" | "PD" | "Dd" | "Os" | "ft"
+            | "fam" | "ps" | "ll" | "ta" | "so" | "ds" | "de" | "nr" => {}
+            "B" | "I" | "SB" | "SM" => {
+                let font = match name {
+                    "I" => Font::Italic,
+                    "SM" => Font::Roman,
+                    _ => Font::Bold,
+                };
+                if args.is_empty() {
+                    self.pending_font = Some(font);
+                } else {
+                    let text = split_args(args).join(" ");
+                    self.push_inline(parse_inline(&text, font));
+                }
+            }
+            "BR" | "BI" | "IB" | "IR" | "RI" | "RB" => {
+                let fonts: Vec<Font> = name
+                    .chars()
+                    .map(|c| Font::from_name(&c.to_string()))
+                    .collect();
+                let nodes = split_args(args)
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, arg)| parse_inline(arg, fonts[i % 2]))
+                    .collect();
+                self.push_inline(nodes);
+            }
+            "nf" | "EX" | "Bd" => {
+                self.end_paragraph();
+                self.literal.get_or_insert_with(String::new);
+            }
+            "fi" | "EE" | "Ed" => {
+                if let Some(literal) = self.literal.take() {
+                    self.end_paragraph();
+                    self.push_block(mdast::code(None, literal.trim_end_matches('\n')));
+                }
+            }
+            "Bl" => {
+                self.end_paragraph();
+                self.end_list();
+            }
+            "El" => self.end_list(),
+            "It" => {
+                let term = self.mdoc_inline(args);
+                self.start_item(term);
+            }
+            "Nm" => {
+                // The first `.Nm name` sets the name that bare `.Nm` repeats
+                if let Some(name) = split_args(args).into_iter().next() {
+                    self.name.get_or_insert(name);
+                }
+                let nodes = match (args.is_empty(), &self.name) {
+                    (false, _) => self.mdoc_inline(&format!("Sy {}", args)),
+                    (true, Some(name)) => vec![Font::Bold.wrap(name)],
+                    (true, None) => Vec::new(),
+                };
+                self.push_inline(nodes);
+            }
+            _ if is_mdoc_macro(name) => {
+                let nodes = self.mdoc_inline(&format!("{} {}", name, args));
+                self.push_inline(nodes);
+            }
+            _ => {}
+        }
+    }
+
+    fn section(&mut self, depth: usize, args: &str) {
+        self.end_paragraph();
+        self.end_list();
+        if args.is_empty() {
+            self.pending_heading = Some(depth);
+        } else {
+            let title = split_args(args).join(" ");
+            self.heading(depth, parse_inline(&title, Font::Roman));
+        }
+    }
+
+    fn heading(&mut self, depth: usize, children: Vec<Value>) {
+        self.blocks.push(mdast::heading(depth, children));
+    }
+
+    fn push_inline(&mut self, nodes: Vec<Value>) {
+        if nodes.is_empty() {
+            return;
+        }
+        let target = match self.items.last_mut() {
+            Some(item) if self.expect_tag => &mut item.term,
+            Some(item) if self.in_item => &mut item.body,
+            _ => &mut self.inline,
+        };
+        if !target.is_empty() {
+            target.push(mdast::text(" "));
+        }
+        target.extend(nodes);
+        self.expect_tag = false;
+    }
+
+    fn push_block(&mut self, block: Value) {
+        if self.in_item {
+            self.end_list();
+        }
+        self.blocks.push(block);
+    }
+
+    fn end_paragraph(&mut self) {
+        if !self.inline.is_empty() {
+            let inline = std::mem::take(&mut self.inline);
+            self.blocks.push(mdast::paragraph(inline));
+        }
+    }
+
+    fn start_item(&mut self, term: Vec<Value>) {
+        self.end_paragraph();
+        self.items.push(Item {
+            term,
+            body: Vec::new(),
+        });
+        self.in_item = true;
+        self.expect_tag = false;
+    }
+
+    fn end_list(&mut self) {
+        self.in_item = false;
+        self.expect_tag = false;
+        if self.items.is_empty() {
+            return;
+        }
+        let items = std::mem::take(&mut self.items)
+            .into_iter()
+            .map(|item| {
+                let body = if item.body.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![mdast::paragraph(item.body)]
+                };
+                mdast::list_item(None, mdast::definition(item.term, body))
+            })
+            .collect();
+        self.blocks.push(mdast::list(false, items));
+    }
+
+    fn finish(mut self) -> Value {
+        if let Some(literal) = self.literal.take() {
+            self.blocks
+                .push(mdast::code(None, literal.trim_end_matches('\n')));
+        }
+        self.end_paragraph();
+        self.end_list();
+        mdast::root(self.blocks)
+    }
+
+    /// Renders the semantic inline macros of mdoc (`.Fl`, `.Ar`, `.Op`, ...),
+    /// which may be nested on a single line.
+    fn mdoc_inline(&self, line: &str) -> Vec<Value> {
+        let mut nodes = Vec::new();
+        let mut font = Font::Roman;
+        let mut closers: Vec<&str> = Vec::new();
+        let mut no_space = true;
+        let mut flag_next = false;
+        let mut cross_reference_words: u8 = 0;
+
+        for token in split_args(line) {
+            let (open, close, next_font) = match token.as_str() {
+                "Fl" => {
+                    flag_next = true;
+                    ("", "", Font::Bold)
+                }
+                "Xr" => {
+                    cross_reference_words = 2;
+                    ("", "", Font::Bold)
+                }
+                "Nm" | "Cm" | "Sy" | "Ic" | "Cd" | "Fn" | "Fd" => ("", "", Font::Bold),
+                "Ar" | "Pa" | "Em" | "Va" | "Ev" | "Fa" => ("", "", Font::Italic),
+                "Li" | "Ql" => ("", "", Font::Mono),
+                "Op" | "Oo" => ("[", "]", Font::Roman),
+                "Dq" | "Do" => ("“", "”", Font::Roman),
+                "Qq" => ("\"", "\"", Font::Roman),
+                "Sq" => ("‘", "’", Font::Roman),
+                "Pq" | "Po" => ("(", ")", Font::Roman),
+                "Bq" => ("[", "]", Font::Roman),
+                "Nd" => {
+                    nodes.push(mdast::text(" — "));
+                    no_space = true;
+                    ("", "", Font::Roman)
+                }
+                "Ns" => {
+                    no_space = true;
+                    continue;
+                }
+                "No" | "It" | "Oc" | "Dc" | "Pc" | "Bc" | "Qc" | "Sc" => ("", "", Font::Roman),
+                word if cross_reference_words == 1 => {
+                    // The section of `.Xr name section`, rendered as name(section)
+                    nodes.push(mdast::text(&format!("({})", word)));
+                    cross_reference_words = 0;
+                    font = Font::Roman;
+                    continue;
+                }
+                word => {
+                    cross_reference_words = cross_reference_words.saturating_sub(1);
+                    let is_punctuation = word.len() == 1
+                        && matches!(word, "." | "," | ";" | ":" | ")" | "]" | "?" | "!");
+                    if !no_space && !is_punctuation {
+                        nodes.push(mdast::text(" "));
+                    }
+                    let word = if flag_next {
+                        format!("-{}", word)
+                    } else {
+                        word.to_string()
+                    };
+                    nodes.extend(parse_inline(&word, font));
+                    no_space = false;
+                    flag_next = false;
+                    continue;
+                }
+            };
+
+            if flag_next && token != "Fl" {
+                // A bare `.Fl` renders a lone dash
+                nodes.push(Font::Bold.wrap("-"));
+                flag_next = false;
+            }
+            if !open.is_empty() {
+                if !no_space {
+                    nodes.push(mdast::text(" "));
+                }
+                nodes.push(mdast::text(open));
+                closers.push(close);
+                no_space = true;
+            }
+            font = next_font;
+        }
+
+        if flag_next {
+            nodes.push(Font::Bold.wrap("-"));
+        }
+        for close in closers.into_iter().rev() {
+            nodes.push(mdast::text(close));
+        }
+        nodes
+    }
+}
+
+fn is_mdoc_macro(name: &str) -> bool {
+    matches!(
+        name,
+        "Nd" | "Fl"
+            | "Ar"
+            | "Op"
+            | "Cm"
+            | "Pa"
+            | "Xr"
+            | "Em"
+            | "Sy"
+            | "Ic"
+            | "Li"
+            | "Ev"
+            | "Va"
+            | "Dq"
+            | "Qq"
+            | "Sq"
+            | "Pq"
+            | "No"
+            | "Ql"
+    )
+}
+
+/// Splits macro arguments on whitespace, honouring double-quoted arguments.
+fn split_args(args: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut chars = args.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut arg = String::new();
+        if c == '"' {
+            chars.next();
+            while let Some(c) = chars.next() {
+                if c == '"' {
+                    // A doubled quote inside a quoted argument is a literal quote
+                    if chars.next_if_eq(&'"').is_some() {
+                        arg.push('"');
+                        continue;
+                    }
+                    break;
+                }
+                arg.push(c);
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                arg.push(c);
+                if c == '\\' {
+                    arg.extend(chars.next());
+                }
+            }
+        }
+        result.push(arg);
+    }
+
+    result
+}
+
+/// Resolves escapes and font changes in a line of roff text into inline
+/// mdast nodes.
+fn parse_inline(line: &str, base_font: Font) -> Vec<Value> {
+    let mut nodes = Vec::new();
+    let mut font = base_font;
+    let mut previous_font = base_font;
+    let mut buffer = String::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            buffer.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('f') => {
+                let name = read_escape_name(&mut chars);
+                let next_font = if name == "P" {
+                    previous_font
+                } else {
+                    Font::from_name(&name)
+                };
+                if next_font != font {
+                    if !buffer.is_empty() {
+                        nodes.push(font.wrap(&buffer));
+                        buffer.clear();
+                    }
+                    previous_font = font;
+                    font = next_font;
+                }
+            }
+            Some('"') => break,
+            Some(c) => buffer.push_str(&resolve_escape(c, &mut chars)),
+            None => {}
+        }
+    }
+
+    if !buffer.is_empty() {
+        nodes.push(font.wrap(&buffer));
+    }
+    nodes
+}
+
+/// Strips font changes and resolves escapes, for literal blocks and tags.
+fn plain_text(line: &str) -> String {
+    let mut result = String::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('f') => {
+                read_escape_name(&mut chars);
+            }
+            Some('"') => break,
+            Some(c) => result.push_str(&resolve_escape(c, &mut chars)),
+            None => {}
+        }
+    }
+    result
+}
+
+/// Reads an escape argument in any of the `X`, `(XX` or `[name]` forms.
+fn read_escape_name(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    match chars.next() {
+        Some('(') => chars.take(2).collect(),
+        Some('[') => chars.by_ref().take_while(|c| *c != ']').collect(),
+        Some(c) => c.to_string(),
+        None => String::new(),
+    }
+}
+
+fn resolve_escape(c: char, chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    match c {
+        '-' | '.' | '\'' => c.to_string(),
+        'e' | '\\' => "\\".to_string(),
+        '&' | '|' | '^' | 'c' | ')' | '%' => String::new(),
+        ' ' | '~' | '0' => " ".to_string(),
+        '(' => {
+            let name: String = chars.take(2).collect();
+            special_character(&name)
+        }
+        '[' => {
+            let name: String = chars.by_ref().take_while(|c| *c != ']').collect();
+            special_character(&name)
+        }
+        '*' => {
+            let name = read_escape_name(chars);
+            special_character(&name)
+        }
+        's' => {
+            // Point size changes such as \s-1 or \s+2 have no terminal meaning
+            chars.next_if(|c| *c == '+' || *c == '-');
+            chars.next_if(char::is_ascii_digit);
+            String::new()
+        }
+        _ => c.to_string(),
+    }
+}
+
+fn special_character(name: &str) -> String {
+    match name {
+        "bu" => "•",
+        "em" => "—",
+        "en" => "–",
+        "hy" | "mi" => "-",
+        "co" => "©",
+        "rg" => "®",
+        "tm" => "™",
+        "lq" | "Lq" => "“",
+        "rq" | "Rq" => "”",
+        "oq" => "‘",
+        "cq" => "’",
+        "aq" => "'",
+        "dq" => "\"",
+        "dg" => "†",
+        "de" => "°",
+        "->" => "→",
+        "<-" => "←",
+        "Fo" => "«",
+        "Fc" => "»",
+        "ti" => "~",
+        "ha" => "^",
+        "rs" => "\\",
+        "ga" => "`",
+        "aa" => "´",
+        "R" => "®",
+        _ => "",
+    }
+    .to_string()
+}
@@ -0,0 +1,159 @@
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+use crate::app;
+use crate::render::{render_image, render_markdown};
+use crate::utils::highlight_code;
+
+const GUTTER_WIDTH: usize = 9;
+
+pub fn render_notebook(content: &str) -> io::Result<()> {
+    let notebook: Value = serde_json::from_str(content)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    let language = notebook_language(&notebook);
+
+    if let Some(cells) = notebook["cells"].as_array() {
+        for cell in cells {
+            match cell["cell_type"].as_str() {
+                Some("markdown") => {
+                    let json = app::parse_and_process_markdown(&join_source(&cell["source"]))?;
+                    render_markdown(&json)?;
+                }
+                Some("code") => render_code_cell(cell, &language)?,
+                _ => render_raw_cell(&join_source(&cell["source"]))?,
+            }
+            println!();
+        }
+    }
+
+    Ok(())
+}
+
+/// The kernel language, taken from the notebook metadata.
+fn notebook_language(notebook: &Value) -> String {
+    let metadata = &notebook["metadata"];
+    metadata["kernelspec"]["language"]
+        .as_str()
+        .or_else(|| metadata["language_info"]["name"].as_str())
+        .unwrap_or("python")
+        .to_lowercase()
+}
+
+/// Notebook text fields are either a single string or a list of lines.
+fn join_source(source: &Value) -> String {
+    match source {
+        Value::String(text) => text.clone(),
+        Value::Array(lines) => lines.iter().filter_map(|line| line.as_str()).collect(),
+        _ => String::new(),
+    }
+}
+
+fn render_code_cell(cell: &Value, language: &str) -> io::Result<()> {
+    let mut stdout = StandardStream::stdout(ColorChoice::Always);
+    let execution_count = match cell["execution_count"].as_u64() {
+        Some(count) => count.to_string(),
+        None => " ".to_string(),
+    };
+
+    let source = join_source(&cell["source"]);
+    for (i, line) in source.lines().enumerate() {
+        stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)).set_bold(true))?;
+        if i == 0 {
+            let gutter = format!("In [{}]:", execution_count);
+            write!(stdout, "{:<width$}", gutter, width = GUTTER_WIDTH)?;
+        } else {
+            write!(stdout, "{}", " ".repeat(GUTTER_WIDTH))?;
+        }
+        stdout.reset()?;
+
+        if let Err(e) = highlight_code(line, language, &mut stdout) {
+            // If highlighting fails, fall back to plain text
+            write!(stdout, "{}", line)?;
+            eprintln!(
+                "Error highlighting code: {}. Falling back to plain text for this line.",
+                e
+            );
+        }
+        stdout.reset()?;
+        writeln!(stdout)?;
+    }
+
+    if let Some(outputs) = cell["outputs"].as_array() {
+        for output in outputs {
+            render_output(output, &execution_count)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn render_output(output: &Value, execution_count: &str) -> io::Result<()> {
+    let mut stdout = StandardStream::stdout(ColorChoice::Always);
+
+    match output["output_type"].as_str() {
+        Some("stream") => {
+            let color = if output["name"] == "stderr" {
+                Color::Red
+            } else {
+                Color::White
+            };
+            stdout.set_color(ColorSpec::new().set_fg(Some(color)))?;
+            print_output_text(&join_source(&output["text"]));
+            stdout.reset()?;
+        }
+        Some("execute_result") | Some("display_data") => {
+            let data = &output["data"];
+            if let Some(png) = data["image/png"].as_str() {
+                // Notebooks wrap base64 payloads over several lines
+                let b64: String = png.split_whitespace().collect();
+                render_image(&json!({
+                    "type": "image",
+                    "url": format!("data:image/png;base64,{}", b64),
+                    "alt": "",
+                }))?;
+            } else if !data["text/plain"].is_null() {
+                if output["output_type"] == "execute_result" {
+                    stdout.set_color(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(true))?;
+                    let gutter = format!("Out[{}]:", execution_count);
+                    println!("{:<width$}", gutter, width = GUTTER_WIDTH);
+                    stdout.reset()?;
+                }
+                print_output_text(&join_source(&data["text/plain"]));
+            }
+        }
+        Some("error") => {
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(true))?;
+            println!(
+                "{}{}: {}",
+                " ".repeat(GUTTER_WIDTH),
+                output["ename"].as_str().unwrap_or("Error"),
+                output["evalue"].as_str().unwrap_or("")
+            );
+            stdout.reset()?;
+            // Tracebacks already carry their own ANSI colours
+            if let Some(traceback) = output["traceback"].as_array() {
+                for frame in traceback.iter().filter_map(|frame| frame.as_str()) {
+                    print_output_text(frame);
+                }
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn print_output_text(text: &str) {
+    for line in text.lines() {
+        println!("{}{}", " ".repeat(GUTTER_WIDTH), line);
+    }
+}
+
+fn render_raw_cell(source: &str) -> io::Result<()> {
+    let mut stdout = StandardStream::stdout(ColorChoice::Always);
+    stdout.set_color(ColorSpec::new().set_fg(Some(Color::White)).set_dimmed(true))?;
+    print_output_text(source);
+    stdout.reset()?;
+    Ok(())
+}
@@ -0,0 +1,494 @@
+use serde_json::Value;
+use std::io;
+
+use crate::app;
+use crate::render::render_markdown;
+use crate::utils::mdast::{self, ListEntry};
+
+/// Keywords recognised at the start of a headline unless the file declares
+/// its own with `#+TODO:`.
+const DEFAULT_KEYWORDS: [&str; 2] = ["TODO", "DONE"];
+
+const IMAGE_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "gif", "svg", "webp", "bmp"];
+
+/// Markers of Org's inline emphasis, like `*bold*` and `~code~`.
+const EMPHASIS_MARKERS: [char; 6] = ['*', '/', '_', '=', '~', '+'];
+
+pub fn render_org(content: &str) -> io::Result<()> {
+    let mut json = org_to_mdast(content);
+    app::process_markdown_ast(&mut json);
+    render_markdown(&json)
+}
+
+/// Converts an Org document to an mdast tree.
+pub fn org_to_mdast(content: &str) -> Value {
+    let lines: Vec<&str> = content.lines().map(str::trim_end).collect();
+    let keywords = todo_keywords(&lines);
+    let mut parser = Parser {
+        keywords,
+        heading_offset: 0,
+    };
+    mdast::root(parser.blocks(&lines))
+}
+
+struct Parser {
+    keywords: Vec<String>,
+    /// Headlines move down a level when `#+TITLE:` takes the top one.
+    heading_offset: usize,
+}
+
+/// Reads the keywords of `#+TODO:` lines, where `|` separates the open
+/// states from the done ones.
+fn todo_keywords(lines: &[&str]) -> Vec<String> {
+    let mut keywords: Vec<String> = lines
+        .iter()
+        .filter_map(|line| {
+            let (key, value) = keyword_line(line)?;
+            matches!(key.as_str(), "TODO" | "SEQ_TODO" | "TYP_TODO").then_some(value)
+        })
+        .flat_map(|value| value.split_whitespace())
+        .filter(|keyword| *keyword != "|")
+        // `WAIT(w@/!)` declares a fast-access key and logging behaviour
+        .map(|keyword| keyword.split('(').next().unwrap_or(keyword).to_string())
+        .collect();
+    if keywords.is_empty() {
+        keywords = DEFAULT_KEYWORDS.iter().map(|k| k.to_string()).collect();
+    }
+    keywords
+}
+
+/// Splits a `#+KEY: value` line into its uppercased key and value.
+fn keyword_line(line: &str) -> Option<(String, &str)> {
+    let rest = line.trim_start().strip_prefix("#+")?;
+    let (key, value) = rest.split_once(':')?;
+    if key.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((key.to_uppercase(), value.trim()))
+}
+
+impl Parser {
+    fn blocks(&mut self, lines: &[&str]) -> Vec<Value> {
+        let mut blocks = Vec::new();
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = lines[i];
+            let trimmed = line.trim_start();
+
+            if trimmed.is_empty() || trimmed == "#" || trimmed.starts_with("# ") {
+                i += 1;
+            } else if let Some(depth) = headline_depth(line) {
+                blocks.push(self.headline(depth, &line[depth + 1..]));
+                i += 1;
+            } else if is_drawer_start(trimmed) {
+                // Property and logbook drawers are metadata, not content
+                i = lines[i + 1..]
+                    .iter()
+                    .position(|line| line.trim().eq_ignore_ascii_case(":END:"))
+                    .map_or(i + 1, |offset| i + offset + 2);
+            } else if is_planning_line(trimmed) {
+                blocks.push(mdast::paragraph(vec![mdast::emphasis(vec![mdast::text(
+                    trimmed,
+                )])]));
+                i += 1;
+            } else if let Some((name, args)) = block_start(trimmed) {
+                let end = lines[i + 1..]
+                    .iter()
+                    .position(|line| is_block_end(line, &name))
+                    .map_or(lines.len(), |offset| i + 1 + offset);
+                let body = dedent(&lines[i + 1..end]);
+                blocks.extend(self.block(&name, args, &body));
+                i = end + 1;
+            } else if let Some((key, value)) = keyword_line(trimmed) {
+                if key == "TITLE" && !value.is_empty() {
+                    blocks.push(mdast::heading(1, parse_inline(value)));
+                    self.heading_offset = 1;
+                }
+                i += 1;
+            } else if trimmed.starts_with('|') {
+                let start = i;
+                while i < lines.len() && lines[i].trim_start().starts_with('|') {
+                    i += 1;
+                }
+                blocks.push(table(&lines[start..i]));
+            } else if trimmed == ":" || trimmed.starts_with(": ") {
+                // Fixed-width lines
+                let start = i;
+                while i < lines.len()
+                    && (lines[i].trim_start() == ":" || lines[i].trim_start().starts_with(": "))
+                {
+                    i += 1;
+                }
+                let body: Vec<&str> = lines[start..i]
+                    .iter()
+                    .map(|line| line.trim_start().get(2..).unwrap_or(""))
+                    .collect();
+                blocks.push(mdast::code(None, &body.join("\n")));
+            } else if trimmed.len() >= 5 && trimmed.chars().all(|c| c == '-') {
+                blocks.push(mdast::thematic_break());
+                i += 1;
+            } else if list_marker(line).is_some() {
+                let (list, next) = self.list(lines, i);
+                blocks.push(list);
+                i = next;
+            } else {
+                let start = i;
+                while i < lines.len() && !lines[i].trim().is_empty() && !starts_element(lines[i]) {
+                    i += 1;
+                }
+                let end = i.max(start + 1);
+                let text = lines[start..end]
+                    .iter()
+                    .map(|line| line.trim())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                blocks.push(mdast::paragraph(parse_inline(&text)));
+                i = end;
+            }
+        }
+
+        blocks
+    }
+
+    /// Builds a heading from a headline, keeping its TODO keyword, priority
+    /// cookie and tags apart from the title.
+    fn headline(&self, depth: usize, text: &str) -> Value {
+        let mut children = Vec::new();
+        let mut title = text.trim();
+
+        let (first, rest) = title.split_once(' ').unwrap_or((title, ""));
+        if self.keywords.iter().any(|keyword| keyword == first) {
+            children.push(mdast::inline_code(first));
+            title = rest.trim_start();
+        }
+        if let Some(rest) = title.strip_prefix("[#") {
+            if let Some((priority, rest)) = rest.split_once(']') {
+                children.push(mdast::strong(vec![mdast::text(&format!(
+                    "[#{}] ",
+                    priority
+                ))]));
+                title = rest.trim_start();
+            }
+        }
+
+        let (title, tags) = split_tags(title);
+        children.extend(parse_inline(title));
+        if !tags.is_empty() {
+            children.push(mdast::text(" "));
+            children.extend(tags.iter().map(|tag| mdast::inline_code(tag)));
+        }
+
+        mdast::heading(depth + self.heading_offset, children)
+    }
+
+    fn block(&mut self, name: &str, args: &str, body: &[&str]) -> Vec<Value> {
+        let admonition = match name {
+            "note" | "info" => Some("note"),
+            "tip" => Some("tip"),
+            "important" => Some("important"),
+            "warning" => Some("warning"),
+            "caution" | "danger" => Some("caution"),
+            _ => None,
+        };
+
+        match name {
+            "src" => {
+                let language = args.split_whitespace().next();
+                vec![mdast::code(language, &body.join("\n"))]
+            }
+            "example" => vec![mdast::code(None, &body.join("\n"))],
+            "quote" => vec![mdast::blockquote(self.blocks(body))],
+            "verse" => body
+                .iter()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| mdast::paragraph(parse_inline(line.trim())))
+                .collect(),
+            "comment" | "export" => Vec::new(),
+            _ => match admonition {
+                Some(kind) => {
+                    let text = body
+                        .iter()
+                        .map(|line| line.trim())
+                        .filter(|line| !line.is_empty())
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    let text: String = parse_inline(&text).iter().map(mdast::node_text).collect();
+                    vec![mdast::admonition(kind, &text)]
+                }
+                None => self.blocks(body),
+            },
+        }
+    }
+
+    fn list(&mut self, lines: &[&str], start: usize) -> (Value, usize) {
+        let mut entries = Vec::new();
+        let base_indent = indent(lines[start]);
+        let top_ordered = list_marker(lines[start]).is_some_and(|(_, ordered, _)| ordered);
+        let mut i = start;
+
+        while let Some((level, ordered, text)) = lines.get(i).and_then(|line| list_marker(line)) {
+            if level < base_indent || (level == base_indent && ordered != top_ordered) {
+                break;
+            }
+            i += 1;
+
+            // Continuation lines are indented past the item's marker
+            let mut text = text.to_string();
+            while let Some(line) = lines.get(i) {
+                if line.trim().is_empty() || indent(line) <= level || starts_element(line) {
+                    break;
+                }
+                text.push(' ');
+                text.push_str(line.trim());
+                i += 1;
+            }
+
+            let (checked, text) = match text.get(..4) {
+                Some("[X] " | "[x] ") => (Some(true), &text[4..]),
+                Some("[ ] " | "[-] ") => (Some(false), &text[4..]),
+                _ => (None, text.as_str()),
+            };
+            let children = match text.split_once(" :: ") {
+                Some((term, description)) => mdast::definition(
+                    vec![mdast::strong(parse_inline(term))],
+                    vec![mdast::paragraph(parse_inline(description))],
+                ),
+                None => vec![mdast::paragraph(parse_inline(text))],
+            };
+            entries.push(ListEntry {
+                level,
+                ordered,
+                checked,
+                children,
+            });
+
+            // A single blank line does not end the list
+            if lines.get(i).is_some_and(|line| line.trim().is_empty())
+                && lines
+                    .get(i + 1)
+                    .is_some_and(|line| list_marker(line).is_some_and(|(l, _, _)| l >= base_indent))
+            {
+                i += 1;
+            }
+        }
+
+        (mdast::nested_list(entries), i)
+    }
+}
+
+fn headline_depth(line: &str) -> Option<usize> {
+    let depth = line.chars().take_while(|c| *c == '*').count();
+    (depth > 0 && line[depth..].starts_with(' ')).then_some(depth)
+}
+
+fn is_drawer_start(line: &str) -> bool {
+    line.len() > 2
+        && line.starts_with(':')
+        && line.ends_with(':')
+        && line[1..line.len() - 1]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn is_planning_line(line: &str) -> bool {
+    ["SCHEDULED:", "DEADLINE:", "CLOSED:"]
+        .iter()
+        .any(|keyword| line.starts_with(keyword))
+}
+
+/// Parses `#+BEGIN_NAME args`, returning the lowercase name and arguments.
+fn block_start(line: &str) -> Option<(String, &str)> {
+    let rest = line
+        .get(..8)?
+        .eq_ignore_ascii_case("#+begin_")
+        .then(|| &line[8..])?;
+    let (name, args) = rest.split_once(' ').unwrap_or((rest, ""));
+    Some((name.to_lowercase(), args.trim()))
+}
+
+fn is_block_end(line: &str, name: &str) -> bool {
+    line.trim()
+        .to_lowercase()
+        .strip_prefix("#+end_")
+        .is_some_and(|end| end == name)
+}
+
+fn starts_element(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    headline_depth(line).is_some()
+        || list_marker(line).is_some()
+        || trimmed.starts_with('|')
+        || trimmed.starts_with("#+")
+        || trimmed.starts_with(": ")
+}
+
+/// Returns the indentation, whether the list is ordered, and the item text.
+fn list_marker(line: &str) -> Option<(usize, bool, &str)> {
+    let level = indent(line);
+    let trimmed = &line[level..];
+    // At the start of a line `*` is a headline, not a bullet
+    let bullet = trimmed.starts_with("- ")
+        || trimmed.starts_with("+ ")
+        || (level > 0 && trimmed.starts_with("* "));
+    if bullet {
+        return Some((level, false, trimmed[2..].trim_start()));
+    }
+
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    let rest = &trimmed[digits..];
+    let text = rest
+        .strip_prefix(". ")
+        .or_else(|| rest.strip_prefix(") "))?;
+    (digits > 0).then(|| (level, true, text.trim_start()))
+}
+
+/// Splits trailing `:tag1:tag2:` off a headline title.
+fn split_tags(title: &str) -> (&str, Vec<&str>) {
+    if let Some((rest, tags)) = title.rsplit_once(char::is_whitespace) {
+        let is_tags =
+            tags.len() > 2 && tags.starts_with(':') && tags.ends_with(':') && !tags.contains("::");
+        if is_tags {
+            let tags = tags.split(':').filter(|tag| !tag.is_empty()).collect();
+            return (rest.trim_end(), tags);
+        }
+    }
+    (title, Vec::new())
+}
+
+fn table(lines: &[&str]) -> Value {
+    let rows = lines
+        .iter()
+        .map(|line| line.trim())
+        // Rules such as `|---+---|` only separate the header
+        .filter(|line| !line.starts_with("|-"))
+        .map(|line| {
+            let line = line.trim_start_matches('|');
+            let line = line.strip_suffix('|').unwrap_or(line);
+            line.split('|')
+                .map(|cell| {
+                    parse_inline(cell.trim())
+                        .iter()
+                        .map(mdast::node_text)
+                        .collect::<String>()
+                })
+                .collect()
+        })
+        .collect();
+    mdast::table(rows)
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn dedent<'a>(lines: &[&'a str]) -> Vec<&'a str> {
+    let common = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indent(line))
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| line.get(common..).unwrap_or(""))
+        .collect()
+}
+
+/// Emphasis may open after whitespace or opening punctuation.
+fn can_open(previous: Option<char>) -> bool {
+    previous.is_none_or(|c| c.is_whitespace() || "-({'\"".contains(c))
+}
+
+/// Emphasis may close before whitespace or punctuation.
+fn can_close(next: Option<char>) -> bool {
+    next.is_none_or(|c| c.is_whitespace() || "-.,;:!?')}[\"".contains(c))
+}
+
+fn parse_inline(text: &str) -> Vec<Value> {
+    let mut nodes = Vec::new();
+    let mut buffer = String::new();
+    let mut rest = text;
+    let mut previous = None;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some((node, length)) = inline_markup(rest, previous) {
+            if !buffer.is_empty() {
+                nodes.push(mdast::text(&buffer));
+                buffer.clear();
+            }
+            nodes.push(node);
+            previous = rest[..length].chars().last();
+            rest = &rest[length..];
+            continue;
+        }
+
+        buffer.push(c);
+        rest = &rest[c.len_utf8()..];
+        previous = Some(c);
+    }
+
+    if !buffer.is_empty() {
+        nodes.push(mdast::text(&buffer));
+    }
+    nodes
+}
+
+fn inline_markup(text: &str, previous: Option<char>) -> Option<(Value, usize)> {
+    if let Some(inner) = text.strip_prefix("[[") {
+        let end = inner.find("]]")?;
+        let (target, description) = match inner[..end].split_once("][") {
+            Some((target, description)) => (target, Some(description)),
+            None => (&inner[..end], None),
+        };
+        return Some((link(target, description), end + 4));
+    }
+
+    if text.starts_with("https://") || text.starts_with("http://") {
+        let end = text.find(char::is_whitespace).unwrap_or(text.len());
+        let url = text[..end].trim_end_matches(['.', ',', ')', ';']);
+        return Some((mdast::link(url, vec![mdast::text(url)]), url.len()));
+    }
+
+    let marker = text
+        .chars()
+        .next()
+        .filter(|c| EMPHASIS_MARKERS.contains(c))?;
+    if !can_open(previous) {
+        return None;
+    }
+    let inner = &text[1..];
+    if inner.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let end = inner.char_indices().find_map(|(i, c)| {
+        let closes = c == marker
+            && i > 0
+            && !inner[..i].ends_with(char::is_whitespace)
+            && can_close(inner[i + 1..].chars().next());
+        closes.then_some(i)
+    })?;
+    let content = &inner[..end];
+
+    let node = match marker {
+        '*' => mdast::strong(parse_inline(content)),
+        '/' | '_' => mdast::emphasis(parse_inline(content)),
+        '+' => mdast::delete(vec![mdast::text(content)]),
+        _ => mdast::inline_code(content),
+    };
+    Some((node, end + 2))
+}
+
+/// A link without a description to an image file shows the image itself.
+fn link(target: &str, description: Option<&str>) -> Value {
+    let url = target.strip_prefix("file:").unwrap_or(target);
+    let is_image = url.rsplit_once('.').is_some_and(|(_, extension)| {
+        IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+    });
+
+    match description {
+        None if is_image => mdast::image(url, url),
+        Some(description) => mdast::link(url, parse_inline(description)),
+        None => mdast::link(url, vec![mdast::text(url)]),
+    }
+}
@@ -0,0 +1,131 @@
+use reqwest::header::CONTENT_TYPE;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempDir;
+use url::Url;
+
+use crate::config::get_config;
+use crate::utils::{client_with_limits, read_body, DownloadLimits};
+
+const DEFAULT_DOCUMENT_MAX_SIZE_MB: u64 = 50;
+const DEFAULT_DOCUMENT_TIMEOUT_SECS: u64 = 30;
+
+/// A document downloaded from an `http(s)://` argument. It is saved under
+/// its own file name so it can be shown exactly like a local file.
+pub struct RemoteDocument {
+    /// Where the document ended up after redirects.
+    pub url: Url,
+    pub path: PathBuf,
+    _temp_dir: TempDir,
+}
+
+/// The URL an argument names, if it is an `http://` or `https://` URL rather
+/// than a local path.
+pub fn remote_url(arg: &Path) -> Option<Url> {
+    let url = Url::parse(arg.to_str()?).ok()?;
+    matches!(url.scheme(), "http" | "https").then_some(url)
+}
+
+pub fn fetch_document(url: &Url) -> io::Result<RemoteDocument> {
+    let limits = document_limits();
+    let response = client_with_limits(&limits)?
+        .get(url.clone())
+        .send()
+        .map_err(|e| io::Error::other(format!("Failed to fetch {}: {}", url, e)))?;
+    if !response.status().is_success() {
+        return Err(io::Error::other(format!(
+            "Failed to fetch {}: HTTP {}",
+            url,
+            response.status()
+        )));
+    }
+
+    let final_url = response.url().clone();
+    let mime = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|mime| mime.trim().to_lowercase());
+    let content = read_body(response, limits.max_size)
+        .map_err(|e| io::Error::new(e.kind(), format!("Failed to fetch {}: {}", url, e)))?;
+
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join(file_name(&final_url, mime.as_deref()));
+    fs::write(&path, &content)?;
+
+    Ok(RemoteDocument {
+        url: final_url,
+        path,
+        _temp_dir: temp_dir,
+    })
+}
+
+/// Documents take longer than images, so they have a timeout and size limit
+/// of their own. Connecting shares `image_connect_timeout`.
+fn document_limits() -> DownloadLimits {
+    let config = get_config();
+    DownloadLimits {
+        timeout: Duration::from_secs(
+            config
+                .document_timeout
+                .unwrap_or(DEFAULT_DOCUMENT_TIMEOUT_SECS),
+        ),
+        max_size: config
+            .document_max_size
+            .unwrap_or(DEFAULT_DOCUMENT_MAX_SIZE_MB)
+            * 1024
+            * 1024,
+        ..DownloadLimits::configured()
+    }
+}
+
+/// Names the download after the last segment of its URL, so the usual
+/// extension-based viewer choice applies. The content type adds an extension
+/// when the URL has none, and wins when the server sent an HTML page, as
+/// GitHub does for `blob` links to Markdown files.
+fn file_name(url: &Url, mime: Option<&str>) -> String {
+    let name = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|segment| !segment.is_empty())
+        .unwrap_or("index");
+    let extension = Path::new(name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase);
+
+    match (mime.and_then(mime_extension), extension.as_deref()) {
+        (Some("html"), Some("html" | "htm" | "xhtml")) => name.to_string(),
+        (Some("html"), _) => format!("{}.html", name),
+        (Some(mime_extension), None) => format!("{}.{}", name, mime_extension),
+        _ => name.to_string(),
+    }
+}
+
+fn mime_extension(mime: &str) -> Option<&'static str> {
+    let extension = match mime {
+        "text/html" | "application/xhtml+xml" => "html",
+        "text/markdown" | "text/x-markdown" => "md",
+        "text/x-rst" => "rst",
+        "text/asciidoc" | "text/x-asciidoc" => "adoc",
+        "text/org" | "text/x-org" => "org",
+        "text/x-diff" | "text/x-patch" => "diff",
+        "application/x-ipynb+json" => "ipynb",
+        "application/json" => "json",
+        "application/xml" | "text/xml" => "xml",
+        "text/css" => "css",
+        "text/javascript" | "application/javascript" => "js",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/bmp" => "bmp",
+        "image/svg+xml" => "svg",
+        "text/plain" => "txt",
+        _ => return None,
+    };
+    Some(extension)
+}
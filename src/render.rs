@@ -3,8 +3,8 @@ use lazy_static::lazy_static;
 use serde_json::json;
 use serde_json::Value;
 use std::collections::HashMap;
-use std::io::{self, Write};
-use std::path::PathBuf;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
 
 use base64::{engine::general_purpose, Engine as _};
 use std::sync::atomic::Ordering;
@@ -13,27 +13,87 @@ use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
 use url::Url;
 
+use crate::animation::render_animated_image;
 use crate::config::get_config;
 use crate::constants::DEBUG_MODE;
-use crate::utils::download_image;
-use crate::utils::highlight_code;
-use crate::utils::shared;
+use crate::git::LineChanges;
+use crate::graphics::{print_image, GraphicsProtocol};
+use crate::line_range::LineSelection;
+use crate::utils::{ast, shared};
+use crate::utils::{download_image, is_svg, orient_image, prefetch_images, rasterize_svg};
+use crate::utils::{highlight_code, highlight_code_with_background};
+
+/// Background of lines picked out with `--highlight-line`.
+const HIGHLIGHTED_LINE_BACKGROUND: Color = Color::Rgb(58, 61, 65);
 
 lazy_static! {
     static ref CURRENT_HEADING_LEVEL: Mutex<usize> = Mutex::new(0);
     static ref CONTENT_INDENT_LEVEL: Mutex<usize> = Mutex::new(0);
     static ref LIST_STACK: Mutex<Vec<usize>> = Mutex::new(Vec::new());
     static ref ORDERED_LIST_STACK: Mutex<Vec<bool>> = Mutex::new(Vec::new());
+    static ref DOCUMENT_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+    static ref REMOTE_DOCUMENT: Mutex<Option<Url>> = Mutex::new(None);
     static ref LINK_DEFINITIONS: Mutex<HashMap<String, (String, Option<String>)>> =
         Mutex::new(HashMap::new());
 }
 
+/// Sets the document whose directory relative image and link paths are
+/// resolved against, or the working directory when there is none.
+pub fn set_document_path(file_path: Option<&str>) {
+    *DOCUMENT_DIR.lock().unwrap() = file_path
+        .and_then(|path| Path::new(path).parent())
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(Path::to_path_buf);
+}
+
+/// Sets the URL a document was downloaded from, which relative paths are
+/// then resolved against instead of the downloaded copy's directory.
+pub fn set_remote_document(url: Option<Url>) {
+    *REMOTE_DOCUMENT.lock().unwrap() = url;
+}
+
+/// Resolves a relative or absolute path in the document against the
+/// document's URL or directory, giving a `file://` URL for local documents.
+/// Returns `None` for URLs with a scheme and for anchors within the document.
+fn resolve_url(url: &str) -> Option<Url> {
+    if url.is_empty() || url.starts_with('#') || Url::parse(url).is_ok() {
+        return None;
+    }
+    if let Some(base) = &*REMOTE_DOCUMENT.lock().unwrap() {
+        return base.join(url).ok();
+    }
+    let dir = match &*DOCUMENT_DIR.lock().unwrap() {
+        Some(dir) => std::path::absolute(dir).ok()?,
+        None => std::env::current_dir().ok()?,
+    };
+    Url::from_directory_path(dir).ok()?.join(url).ok()
+}
+
 pub fn render_markdown(ast: &Value) -> io::Result<()> {
+    prefetch_remote_images(ast);
     render_node(ast)?;
     render_footnotes()?;
     Ok(())
 }
 
+/// Downloads all remote images of a document at once, instead of one by one
+/// as rendering reaches them.
+fn prefetch_remote_images(ast: &Value) {
+    let config = get_config();
+    if !config.render_images || GraphicsProtocol::configured() == GraphicsProtocol::None {
+        return;
+    }
+    let mut urls = Vec::new();
+    ast::collect_image_urls(ast, &mut urls);
+    let remote_urls: Vec<String> = urls
+        .iter()
+        .filter_map(|url| resolve_url(url).or_else(|| Url::parse(url).ok()))
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .map(String::from)
+        .collect();
+    prefetch_images(&remote_urls);
+}
+
 fn render_node(node: &Value) -> io::Result<()> {
     let config = get_config();
 
@@ -371,14 +431,18 @@ fn render_link(node: &Value) -> io::Result<()> {
     let config = get_config();
     let mut stdout = StandardStream::stdout(ColorChoice::Always);
     let url = node["url"].as_str().unwrap_or("");
+    // Local files open through file:// URLs, which work from any directory
+    let url = resolve_url(url).map_or_else(|| url.to_string(), String::from);
 
     if config.render_links {
         render_children(node)?;
     } else {
         // Add a space before the link reference
         print!(" ");
-        // Start OSC 8 hyperlink
-        print!("\x1B]8;;{}\x1B\\", url);
+        // Start OSC 8 hyperlink, which plain output should not carry
+        if config.use_colors {
+            print!("\x1B]8;;{}\x1B\\", url);
+        }
 
         stdout.set_color(
             ColorSpec::new()
@@ -391,7 +455,9 @@ fn render_link(node: &Value) -> io::Result<()> {
         stdout.reset()?;
 
         // End OSC 8 hyperlink
-        print!("\x1B]8;;\x1B\\");
+        if config.use_colors {
+            print!("\x1B]8;;\x1B\\");
+        }
 
         // Add a space after the link reference
         print!(" ");
@@ -402,12 +468,16 @@ fn render_link(node: &Value) -> io::Result<()> {
 
 pub fn render_image(node: &Value) -> io::Result<()> {
     let config = get_config();
-    if !config.render_images {
+    if !config.render_images || GraphicsProtocol::configured() == GraphicsProtocol::None {
         println!("[Image: {}]", node["alt"].as_str().unwrap_or(""));
         return Ok(());
     }
 
     let url = node["url"].as_str().unwrap_or("");
+    let alt = node["alt"]
+        .as_str()
+        .filter(|alt| !alt.is_empty())
+        .unwrap_or(url);
 
     if url.starts_with("data:image") {
         // Handle base64 encoded image
@@ -423,21 +493,32 @@ pub fn render_image(node: &Value) -> io::Result<()> {
             temp_file.write_all(&decoded)?;
             let temp_path = temp_file.into_temp_path();
 
-            render_image_file(temp_path.to_str().unwrap())
+            render_image_source(temp_path.to_str().unwrap(), alt)
         } else {
             Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 "Invalid base64 image data",
             ))
         }
+    } else if let Some(resolved) = resolve_url(url) {
+        match resolved.to_file_path() {
+            Ok(path) => render_image_source(&path.to_string_lossy(), alt),
+            Err(()) => render_image_source(resolved.as_str(), alt),
+        }
     } else {
-        render_image_file(url)
+        render_image_source(url, alt)
     }
 }
 
 pub fn render_image_file(path: &str) -> io::Result<()> {
+    render_image_source(path, path)
+}
+
+/// Prints a local or remote image, or a placeholder naming it by `alt` when
+/// it cannot be loaded.
+fn render_image_source(path: &str, alt: &str) -> io::Result<()> {
     let config = get_config();
-    if !config.render_images {
+    if !config.render_images || GraphicsProtocol::configured() == GraphicsProtocol::None {
         println!("[Image: {}]", path);
         return Ok(());
     }
@@ -445,14 +526,14 @@ pub fn render_image_file(path: &str) -> io::Result<()> {
     let local_path = if Url::parse(path).is_ok() {
         match download_image(path) {
             Ok(path) => path,
-            Err(_) => return Ok(()), // Silently ignore download errors
+            Err(e) => return render_image_placeholder(alt, &e.to_string()),
         }
     } else {
         PathBuf::from(path)
     };
 
     if !local_path.exists() {
-        return Ok(()); // Silently ignore if the file doesn't exist
+        return render_image_placeholder(alt, "not found");
     }
 
     let viuer_config = viuer::Config {
@@ -462,7 +543,36 @@ pub fn render_image_file(path: &str) -> io::Result<()> {
         ..Default::default()
     };
 
-    if let Err(e) = viuer::print_from_file(&local_path, &viuer_config) {
+    if render_animated_image(&local_path, &viuer_config)? {
+        return Ok(());
+    }
+
+    // viuer only decodes bitmaps, so vector images are rasterized first
+    let result = if is_svg(&local_path) {
+        let (columns, rows) = crossterm::terminal::size().unwrap_or((80, 24));
+        rasterize_svg(
+            &local_path,
+            config.max_image_width.unwrap_or(u32::from(columns)),
+            config.max_image_height.unwrap_or(u32::from(rows)),
+        )
+        .and_then(|(png, columns)| {
+            // Only the width is given so viuer keeps the aspect ratio
+            let svg_config = viuer::Config {
+                width: Some(columns),
+                height: None,
+                ..viuer_config
+            };
+            print_png(&png, &svg_config)
+        })
+    } else {
+        // viuer ignores EXIF orientation, so rotated photos are turned upright first
+        match orient_image(&local_path) {
+            Ok(Some(png)) => print_png(&png, &viuer_config),
+            _ => print_image(&local_path, &viuer_config),
+        }
+    };
+
+    if let Err(e) = result {
         // Silently ignore errors when rendering images
         if config.debug_mode {
             eprintln!("Error rendering image: {}", e);
@@ -472,6 +582,22 @@ pub fn render_image_file(path: &str) -> io::Result<()> {
     Ok(())
 }
 
+fn render_image_placeholder(alt: &str, reason: &str) -> io::Result<()> {
+    let mut stdout = get_stdout();
+    stdout.set_color(ColorSpec::new().set_fg(Some(Color::Red)).set_dimmed(true))?;
+    write!(stdout, "[image: {} — failed: {}]", alt, reason)?;
+    stdout.reset()?;
+    writeln!(stdout)
+}
+
+/// Prints PNG bytes through a temporary file, since viuer links a different
+/// version of the `image` crate.
+fn print_png(png: &[u8], viuer_config: &viuer::Config) -> io::Result<(u32, u32)> {
+    let mut temp_file = tempfile::NamedTempFile::new()?;
+    temp_file.write_all(png)?;
+    print_image(temp_file.path(), viuer_config)
+}
+
 fn render_emphasis(node: &Value) -> io::Result<()> {
     let mut stdout = StandardStream::stdout(ColorChoice::Always);
     stdout.set_color(ColorSpec::new().set_italic(true))?;
@@ -682,6 +808,7 @@ fn parse_admonition(node: &Value) -> Option<(AdmonitionType, String)> {
     None
 }
 
+#[allow(clippy::format_in_format_args)]
 fn render_admonition(admonition_type: AdmonitionType, content: &str) -> io::Result<()> {
     let mut stdout = StandardStream::stdout(ColorChoice::Always);
     stdout.set_color(
@@ -732,25 +859,113 @@ fn render_blockquote(node: &Value) -> io::Result<()> {
     }
 }
 
-pub fn render_code_file(content: &str, mut language: &str) -> io::Result<()> {
-    let mut stdout = get_stdout();
-    let lines: Vec<&str> = content.lines().collect();
-    let line_count = lines.len();
-    let max_line_num_width = line_count.to_string().len();
-    let config = get_config();
+pub fn render_code_file(content: &str, language: &str) -> io::Result<()> {
+    let line_count = content.lines().count();
+    let mut renderer = CodeRenderer::new(language, line_count.to_string().len(), None);
+    for line in content.lines() {
+        if renderer.is_done() {
+            break;
+        }
+        renderer.render_line(line)?;
+    }
+    Ok(())
+}
 
-    for (i, line) in lines.iter().enumerate() {
-        if config.show_line_numbers && config.use_colors {
-            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
-            write!(stdout, "{:>width$} │ ", i + 1, width = max_line_num_width)?;
-            stdout.reset()?;
+/// Renders code as it is read, one line at a time, so files of any size and
+/// endless input show up straight away. Lines changed since the last commit
+/// are marked in the gutter.
+pub fn stream_code<R: BufRead>(
+    reader: R,
+    language: &str,
+    line_number_width: usize,
+    changes: Option<LineChanges>,
+) -> io::Result<()> {
+    let mut renderer = CodeRenderer::new(language, line_number_width, changes);
+    for line in reader.lines() {
+        // The rest of a large file is not even read
+        if renderer.is_done() {
+            break;
         }
+        renderer.render_line(&line?)?;
+    }
+    Ok(())
+}
+
+/// Renders code line by line, leaving out lines outside the selected range
+/// and highlighting the selected lines.
+struct CodeRenderer {
+    language: String,
+    line_number: usize,
+    line_number_width: usize,
+    selection: LineSelection,
+    changes: Option<LineChanges>,
+    stdout: Box<dyn WriteColor>,
+}
+
+impl CodeRenderer {
+    fn new(language: &str, line_number_width: usize, changes: Option<LineChanges>) -> Self {
+        let config = get_config();
+        CodeRenderer {
+            language: if config.use_colors { language } else { "txt" }.to_string(),
+            line_number: 0,
+            line_number_width,
+            selection: LineSelection::current(),
+            changes,
+            stdout: get_stdout(),
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.selection.is_past(self.line_number + 1)
+    }
+
+    fn render_line(&mut self, line: &str) -> io::Result<()> {
+        let config = get_config();
+        let stdout = &mut self.stdout;
+        self.line_number += 1;
+        if !self.selection.shows(self.line_number) {
+            return Ok(());
+        }
+        let background = (config.use_colors && self.selection.highlights(self.line_number))
+            .then_some(HIGHLIGHTED_LINE_BACKGROUND);
 
-        if !config.use_colors {
-            language = "txt";
+        if config.show_line_numbers && config.use_colors {
+            let number_color = ColorSpec::new()
+                .set_fg(Some(Color::Cyan))
+                .set_bg(background)
+                .set_bold(background.is_some())
+                .clone();
+            stdout.set_color(&number_color)?;
+            write!(
+                stdout,
+                "{:>width$} ",
+                self.line_number,
+                width = self.line_number_width
+            )?;
+            // Changed lines show a marker in place of the separator
+            match self
+                .changes
+                .as_ref()
+                .and_then(|changes| changes.get(&self.line_number))
+            {
+                Some(change) => {
+                    let (marker, color) = change.marker();
+                    stdout.set_color(
+                        ColorSpec::new()
+                            .set_fg(Some(color))
+                            .set_bg(background)
+                            .set_bold(true),
+                    )?;
+                    write!(stdout, "{}", marker)?;
+                    stdout.set_color(&number_color)?;
+                }
+                None => write!(stdout, "│")?,
+            }
+            write!(stdout, " ")?;
+            stdout.reset()?;
         }
 
-        if let Err(e) = highlight_code(line, language, &mut stdout) {
+        if let Err(e) = highlight_code_with_background(line, &self.language, stdout, background) {
             // If highlighting fails, fall back to plain text
             writeln!(stdout, "{}", line)?;
             eprintln!(
@@ -759,9 +974,8 @@ pub fn render_code_file(content: &str, mut language: &str) -> io::Result<()> {
             );
         }
         writeln!(stdout)?;
+        stdout.flush()
     }
-
-    Ok(())
 }
 
 pub fn get_indent() -> String {
@@ -772,7 +986,7 @@ pub fn get_indent() -> String {
     }
 }
 
-fn get_stdout() -> Box<dyn WriteColor> {
+pub fn get_stdout() -> Box<dyn WriteColor> {
     let config = get_config();
     if config.use_colors {
         Box::new(StandardStream::stdout(ColorChoice::Always))
@@ -0,0 +1,622 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io;
+
+use crate::app;
+use crate::render::render_markdown;
+use crate::utils::mdast;
+
+/// Characters that may underline (and overline) a section title.
+const ADORNMENT_CHARS: &str = "=-~^\"'`:+*#.<>_";
+
+/// Directives whose body is shown as a code block.
+const CODE_DIRECTIVES: [&str; 4] = ["code-block", "code", "sourcecode", "highlight"];
+
+pub fn render_rst(content: &str) -> io::Result<()> {
+    let mut json = rst_to_mdast(content);
+    app::process_markdown_ast(&mut json);
+    render_markdown(&json)
+}
+
+/// Converts reStructuredText to an mdast tree.
+pub fn rst_to_mdast(content: &str) -> Value {
+    let lines: Vec<String> = content
+        .replace('\t', "        ")
+        .lines()
+        .map(|line| line.trim_end().to_string())
+        .collect();
+    let mut context = Context {
+        targets: collect_targets(&lines),
+        title_styles: Vec::new(),
+    };
+    mdast::root(parse_blocks(&lines, &mut context))
+}
+
+struct Context {
+    /// Hyperlink targets (`.. _name: url`), keyed by lowercase name.
+    targets: HashMap<String, String>,
+    /// Title adornments in order of first use; the position is the depth.
+    title_styles: Vec<(char, bool)>,
+}
+
+impl Context {
+    fn heading_depth(&mut self, style: (char, bool)) -> usize {
+        match self.title_styles.iter().position(|s| *s == style) {
+            Some(index) => index + 1,
+            None => {
+                self.title_styles.push(style);
+                self.title_styles.len()
+            }
+        }
+    }
+}
+
+fn collect_targets(lines: &[String]) -> HashMap<String, String> {
+    lines
+        .iter()
+        .filter_map(|line| {
+            let target = line.trim_start().strip_prefix(".. _")?;
+            let (name, url) = target.split_once(": ")?;
+            Some((
+                name.trim_matches('`').to_lowercase(),
+                url.trim().to_string(),
+            ))
+        })
+        .collect()
+}
+
+fn parse_blocks(lines: &[String], context: &mut Context) -> Vec<Value> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = &lines[i];
+        if line.is_empty() {
+            i += 1;
+            continue;
+        }
+
+        if indent(line) > 0 {
+            let (body, next) = indented_block(lines, i);
+            blocks.push(mdast::blockquote(parse_blocks(&body, context)));
+            i = next;
+        } else if let Some(style) = overlined_title(lines, i) {
+            let depth = context.heading_depth((style, true));
+            blocks.push(mdast::heading(
+                depth,
+                parse_inline(lines[i + 1].trim(), &context.targets),
+            ));
+            i += 3;
+        } else if let Some(style) = underlined_title(lines, i) {
+            let depth = context.heading_depth((style, false));
+            blocks.push(mdast::heading(depth, parse_inline(line, &context.targets)));
+            i += 2;
+        } else if is_adornment(line) && line.len() >= 4 {
+            blocks.push(mdast::thematic_break());
+            i += 1;
+        } else if line == ".." || line.starts_with(".. ") {
+            let (body, next) = indented_block(lines, i + 1);
+            if let Some((name, args)) = line[2..].split_once("::") {
+                blocks.extend(directive(name.trim(), args.trim(), &body, context));
+            }
+            // Anything else is a comment or a hyperlink target
+            i = next;
+        } else if list_marker(line).is_some() {
+            let (list, next) = parse_list(lines, i, context);
+            blocks.push(list);
+            i = next;
+        } else if is_simple_table_border(line) {
+            let (rows, next) = parse_simple_table(lines, i);
+            blocks.push(mdast::table(rows));
+            i = next;
+        } else if is_grid_table_border(line) {
+            let (rows, next) = parse_grid_table(lines, i);
+            blocks.push(mdast::table(rows));
+            i = next;
+        } else if lines.get(i + 1).is_some_and(|next| indent(next) > 0) && !line.ends_with("::") {
+            let (list, next) = parse_definition_list(lines, i, context);
+            blocks.push(list);
+            i = next;
+        } else {
+            let start = i;
+            while i < lines.len()
+                && !lines[i].is_empty()
+                && indent(&lines[i]) == 0
+                && (i == start || underlined_title(lines, i).is_none())
+            {
+                i += 1;
+            }
+            let text = lines[start..i]
+                .iter()
+                .map(|line| line.trim())
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            // A paragraph ending in `::` introduces the literal block after it
+            let literal = text.ends_with("::");
+            let text = if text == "::" {
+                ""
+            } else if let Some(text) = text.strip_suffix(" ::") {
+                text
+            } else if literal {
+                &text[..text.len() - 1]
+            } else {
+                &text
+            };
+            if !text.is_empty() {
+                blocks.push(mdast::paragraph(parse_inline(text, &context.targets)));
+            }
+
+            if literal {
+                let next = next_non_blank(lines, i);
+                if next < lines.len() && indent(&lines[next]) > 0 {
+                    let (body, after) = indented_block(lines, next);
+                    blocks.push(mdast::code(None, &body.join("\n")));
+                    i = after;
+                }
+            }
+        }
+    }
+
+    blocks
+}
+
+fn directive(name: &str, args: &str, body: &[String], context: &mut Context) -> Vec<Value> {
+    let (options, content) = split_options(body);
+    let text = || {
+        let content = content
+            .iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        plain_text(format!("{} {}", args, content).trim())
+    };
+
+    match name {
+        _ if CODE_DIRECTIVES.contains(&name) => {
+            let language = (!args.is_empty()).then_some(args);
+            vec![mdast::code(language, content.join("\n").trim_matches('\n'))]
+        }
+        "note" | "seealso" | "admonition" => vec![mdast::admonition("note", &text())],
+        "tip" | "hint" => vec![mdast::admonition("tip", &text())],
+        "important" => vec![mdast::admonition("important", &text())],
+        "warning" => vec![mdast::admonition("warning", &text())],
+        "caution" | "danger" | "error" | "attention" => {
+            vec![mdast::admonition("caution", &text())]
+        }
+        "image" | "figure" => {
+            let alt = options.get("alt").map(String::as_str).unwrap_or("");
+            let mut blocks = vec![mdast::paragraph(vec![mdast::image(args, alt)])];
+            // A figure's body is its caption
+            if !content.is_empty() {
+                blocks.extend(parse_blocks(content, context));
+            }
+            blocks
+        }
+        "list-table" => {
+            let mut blocks = Vec::new();
+            if !args.is_empty() {
+                blocks.push(mdast::paragraph(vec![mdast::strong(parse_inline(
+                    args,
+                    &context.targets,
+                ))]));
+            }
+            blocks.push(mdast::table(parse_list_table(content)));
+            blocks
+        }
+        "raw" | "contents" | "toctree" | "index" | "meta" | "include" | "only" => Vec::new(),
+        // Containers such as `topic` or `container` just show their content
+        _ => parse_blocks(content, context),
+    }
+}
+
+/// Splits a directive body into its `:name: value` options and its content.
+fn split_options(body: &[String]) -> (HashMap<String, String>, &[String]) {
+    let mut options = HashMap::new();
+    let mut i = 0;
+    while let Some(line) = body.get(i) {
+        let Some((name, value)) = line.strip_prefix(':').and_then(|rest| rest.split_once(':'))
+        else {
+            break;
+        };
+        options.insert(name.to_string(), value.trim().to_string());
+        i += 1;
+    }
+    (options, &body[i..])
+}
+
+fn parse_list(lines: &[String], start: usize, context: &mut Context) -> (Value, usize) {
+    let ordered = list_marker(&lines[start]).is_some_and(|(ordered, _)| ordered);
+    let mut items = Vec::new();
+    let mut i = start;
+
+    while let Some((item_ordered, width)) = lines.get(i).and_then(|line| list_marker(line)) {
+        if item_ordered != ordered {
+            break;
+        }
+        let (mut body, next) = indented_block(lines, i + 1);
+        body.insert(0, lines[i][width..].trim_start().to_string());
+        items.push(mdast::list_item(None, parse_blocks(&body, context)));
+
+        i = next_non_blank(lines, next);
+        if i < lines.len() && indent(&lines[i]) > 0 {
+            break;
+        }
+    }
+
+    (mdast::list(ordered, items), i)
+}
+
+fn parse_definition_list(lines: &[String], start: usize, context: &mut Context) -> (Value, usize) {
+    let mut items = Vec::new();
+    let mut i = start;
+
+    while i + 1 < lines.len()
+        && !lines[i].is_empty()
+        && indent(&lines[i]) == 0
+        && !lines[i + 1].is_empty()
+        && indent(&lines[i + 1]) > 0
+    {
+        let (body, next) = indented_block(lines, i + 1);
+        let term = vec![mdast::strong(parse_inline(&lines[i], &context.targets))];
+        let children = mdast::definition(term, parse_blocks(&body, context));
+        items.push(mdast::list_item(None, children));
+        i = next_non_blank(lines, next);
+    }
+
+    (mdast::list(false, items), i)
+}
+
+/// Returns whether the line starts a list item, and the width of its marker.
+fn list_marker(line: &str) -> Option<(bool, usize)> {
+    if line.starts_with("- ") || line.starts_with("* ") || line.starts_with("+ ") {
+        return Some((false, 2));
+    }
+
+    let (label, rest) = if let Some(rest) = line.strip_prefix('(') {
+        let end = rest.find(')')?;
+        (&rest[..end], &rest[end + 1..])
+    } else {
+        let end = line.find(['.', ')'])?;
+        (&line[..end], &line[end + 1..])
+    };
+    let is_label = label == "#"
+        || (!label.is_empty() && label.chars().all(|c| c.is_ascii_digit()))
+        || (label.len() == 1 && label.chars().all(|c| c.is_ascii_alphabetic()));
+    if is_label && rest.starts_with(' ') {
+        Some((true, line.len() - rest.len() + 1))
+    } else {
+        None
+    }
+}
+
+fn is_adornment(line: &str) -> bool {
+    let mut chars = line.chars();
+    match chars.next() {
+        Some(first) if ADORNMENT_CHARS.contains(first) => {
+            line.len() >= 2 && chars.all(|c| c == first)
+        }
+        _ => false,
+    }
+}
+
+fn underlined_title(lines: &[String], i: usize) -> Option<char> {
+    let title = lines.get(i)?;
+    let underline = lines.get(i + 1)?;
+    let long_enough = underline.chars().count() >= title.trim().chars().count().min(4);
+    if indent(title) == 0 && !title.is_empty() && is_adornment(underline) && long_enough {
+        underline.chars().next()
+    } else {
+        None
+    }
+}
+
+fn overlined_title(lines: &[String], i: usize) -> Option<char> {
+    let overline = lines.get(i)?;
+    let title = lines.get(i + 1)?;
+    let underline = lines.get(i + 2)?;
+    if is_adornment(overline) && !title.trim().is_empty() && overline == underline {
+        overline.chars().next()
+    } else {
+        None
+    }
+}
+
+fn is_simple_table_border(line: &str) -> bool {
+    line.starts_with("==") && line.contains(' ') && line.chars().all(|c| c == '=' || c == ' ')
+}
+
+fn is_grid_table_border(line: &str) -> bool {
+    (line.starts_with("+-") || line.starts_with("+=")) && line.ends_with('+')
+}
+
+fn parse_simple_table(lines: &[String], start: usize) -> (Vec<Vec<String>>, usize) {
+    let border: Vec<char> = lines[start].chars().collect();
+    let mut column_starts = Vec::new();
+    for (i, c) in border.iter().enumerate() {
+        if *c == '=' && (i == 0 || border[i - 1] == ' ') {
+            column_starts.push(i);
+        }
+    }
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut i = start + 1;
+    while i < lines.len() {
+        let line = &lines[i];
+        i += 1;
+        if is_simple_table_border(line) || (line.starts_with("--") && line.contains(' ')) {
+            // The closing border is the one followed by a blank line
+            if is_simple_table_border(line) && lines.get(i).is_none_or(|next| next.is_empty()) {
+                break;
+            }
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        let chars: Vec<char> = line.chars().collect();
+        let cells: Vec<String> = column_starts
+            .iter()
+            .enumerate()
+            .map(|(j, &from)| {
+                // The last column takes the rest of the line
+                let to = match column_starts.get(j + 1) {
+                    Some(&next) => next.min(chars.len()),
+                    None => chars.len(),
+                };
+                let from = from.min(to);
+                plain_text(chars[from..to].iter().collect::<String>().trim())
+            })
+            .collect();
+
+        // A row with an empty first column continues the previous one
+        match rows.last_mut() {
+            Some(previous) if cells[0].is_empty() => {
+                for (cell, text) in previous.iter_mut().zip(cells) {
+                    if !text.is_empty() {
+                        cell.push(' ');
+                        cell.push_str(&text);
+                    }
+                }
+            }
+            _ => rows.push(cells),
+        }
+    }
+
+    (rows, i)
+}
+
+fn parse_grid_table(lines: &[String], start: usize) -> (Vec<Vec<String>>, usize) {
+    let boundaries: Vec<usize> = lines[start]
+        .chars()
+        .enumerate()
+        .filter(|(_, c)| *c == '+')
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut rows = Vec::new();
+    let mut current = vec![String::new(); boundaries.len().saturating_sub(1)];
+    let mut i = start + 1;
+    while i < lines.len() && (lines[i].starts_with('|') || lines[i].starts_with('+')) {
+        let chars: Vec<char> = lines[i].chars().collect();
+        if chars[0] == '+' {
+            rows.push(std::mem::replace(
+                &mut current,
+                vec![String::new(); boundaries.len().saturating_sub(1)],
+            ));
+        } else {
+            for (cell, bounds) in current.iter_mut().zip(boundaries.windows(2)) {
+                let from = (bounds[0] + 1).min(chars.len());
+                let to = bounds[1].min(chars.len());
+                let text: String = chars[from..to].iter().collect();
+                let text = text.trim();
+                if !text.is_empty() {
+                    if !cell.is_empty() {
+                        cell.push(' ');
+                    }
+                    cell.push_str(&plain_text(text));
+                }
+            }
+        }
+        i += 1;
+    }
+
+    (rows, i)
+}
+
+/// Reads the rows of a `list-table`, written as a bullet list of bullet lists.
+fn parse_list_table(content: &[String]) -> Vec<Vec<String>> {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for line in content {
+        let trimmed = line.trim();
+        if let Some(cell) = trimmed.strip_prefix("* - ") {
+            rows.push(vec![plain_text(cell.trim())]);
+        } else if let Some(cell) = trimmed.strip_prefix("- ") {
+            if let Some(row) = rows.last_mut() {
+                row.push(plain_text(cell.trim()));
+            }
+        } else if !trimmed.is_empty() {
+            if let Some(cell) = rows.last_mut().and_then(|row| row.last_mut()) {
+                cell.push(' ');
+                cell.push_str(&plain_text(trimmed));
+            }
+        }
+    }
+    rows
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn next_non_blank(lines: &[String], mut i: usize) -> usize {
+    while i < lines.len() && lines[i].is_empty() {
+        i += 1;
+    }
+    i
+}
+
+/// Collects the indented lines from `start` on, with the common indentation
+/// removed, and returns them with the index of the first line after them.
+fn indented_block(lines: &[String], start: usize) -> (Vec<String>, usize) {
+    let mut end = start;
+    while end < lines.len() && (lines[end].is_empty() || indent(&lines[end]) > 0) {
+        end += 1;
+    }
+    let mut block = &lines[start..end];
+    while block.last().is_some_and(|line| line.is_empty()) {
+        block = &block[..block.len() - 1];
+    }
+
+    let common = block
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| indent(line))
+        .min()
+        .unwrap_or(0);
+    let body = block
+        .iter()
+        .map(|line| line.get(common..).unwrap_or("").to_string())
+        .collect();
+    (body, end)
+}
+
+/// Inline markup may only start after whitespace or opening punctuation.
+fn can_open(previous: Option<char>) -> bool {
+    previous.is_none_or(|c| c.is_whitespace() || "([{<'\"-/:".contains(c))
+}
+
+fn parse_inline(text: &str, targets: &HashMap<String, String>) -> Vec<Value> {
+    let mut nodes = Vec::new();
+    let mut buffer = String::new();
+    let mut rest = text;
+    let mut previous = None;
+
+    let flush = |buffer: &mut String, nodes: &mut Vec<Value>| {
+        if !buffer.is_empty() {
+            nodes.push(mdast::text(buffer));
+            buffer.clear();
+        }
+    };
+
+    while let Some(c) = rest.chars().next() {
+        let opens = can_open(previous);
+        let markup = if !opens {
+            None
+        } else if let Some(inner) = rest.strip_prefix("``") {
+            inner
+                .find("``")
+                .map(|end| (mdast::inline_code(&inner[..end]), 4 + end))
+        } else if let Some(inner) = rest.strip_prefix("**") {
+            inner
+                .find("**")
+                .filter(|&end| end > 0)
+                .map(|end| (mdast::strong(vec![mdast::text(&inner[..end])]), 4 + end))
+        } else if let Some(inner) = rest.strip_prefix('*') {
+            inner
+                .find('*')
+                .filter(|&end| end > 0 && !inner.starts_with(' '))
+                .map(|end| (mdast::emphasis(vec![mdast::text(&inner[..end])]), 2 + end))
+        } else if let Some(inner) = rest.strip_prefix('`') {
+            inner.find('`').map(|end| {
+                let reference = &inner[..end];
+                let after = &inner[end + 1..];
+                let suffix = after.len() - after.trim_start_matches('_').len();
+                let node = if suffix > 0 {
+                    reference_link(reference, targets)
+                } else {
+                    mdast::emphasis(vec![mdast::text(reference)])
+                };
+                (node, 2 + end + suffix)
+            })
+        } else if c == ':' {
+            role(rest)
+        } else {
+            None
+        };
+
+        if let Some((node, length)) = markup {
+            flush(&mut buffer, &mut nodes);
+            nodes.push(node);
+            previous = rest[..length].chars().last();
+            rest = &rest[length..];
+            continue;
+        }
+
+        if c == '\\' {
+            rest = &rest[1..];
+            if let Some(escaped) = rest.chars().next() {
+                buffer.push(escaped);
+                rest = &rest[escaped.len_utf8()..];
+            }
+        } else {
+            buffer.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        previous = Some(c);
+    }
+
+    flush(&mut buffer, &mut nodes);
+    nodes
+}
+
+/// Resolves `` `text <url>`_ `` and `` `name`_ `` references.
+fn reference_link(reference: &str, targets: &HashMap<String, String>) -> Value {
+    if let Some((label, url)) = reference
+        .strip_suffix('>')
+        .and_then(|reference| reference.rsplit_once('<'))
+    {
+        let label = label.trim();
+        let label = if label.is_empty() { url } else { label };
+        return mdast::link(url, vec![mdast::text(label)]);
+    }
+    match targets.get(&reference.to_lowercase()) {
+        Some(url) => mdast::link(url, vec![mdast::text(reference)]),
+        None => mdast::text(reference),
+    }
+}
+
+/// Parses an interpreted text role such as `` :code:`x` `` or
+/// `` :py:func:`name` ``.
+fn role(text: &str) -> Option<(Value, usize)> {
+    // Search past the opening colon, which in `` :`x` `` is also the closing one
+    let role_end = text.get(1..)?.find(":`")? + 1;
+    let name = &text[1..role_end];
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':'))
+    {
+        return None;
+    }
+    let inner = &text[role_end + 2..];
+    let end = inner.find('`')?;
+    let content = &inner[..end];
+
+    let node = match name {
+        "code" | "literal" | "samp" | "file" | "command" | "kbd" | "envvar" => {
+            mdast::inline_code(content)
+        }
+        "strong" => mdast::strong(vec![mdast::text(content)]),
+        "emphasis" | "title-reference" | "dfn" => mdast::emphasis(vec![mdast::text(content)]),
+        // Cross-reference roles show their title, or the target itself
+        _ => {
+            let label = match content.strip_suffix('>').and_then(|c| c.rsplit_once('<')) {
+                Some((label, _)) => label.trim(),
+                None => content.trim_start_matches('~'),
+            };
+            mdast::inline_code(label)
+        }
+    };
+    Some((node, role_end + 3 + end))
+}
+
+/// Drops inline markup characters, for places that only hold plain text.
+fn plain_text(text: &str) -> String {
+    parse_inline(text, &HashMap::new())
+        .iter()
+        .map(mdast::node_text)
+        .collect()
+}
@@ -0,0 +1,245 @@
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use std::path::Path;
+use termcolor::{Color, ColorSpec, WriteColor};
+
+use crate::config::get_config;
+use crate::directory_tree::{TREE_BRANCH, TREE_INDENT, TREE_LAST_BRANCH, TREE_VERTICAL};
+use crate::render::{get_stdout, render_markdown};
+use crate::utils::read_head;
+
+const SQLITE_MAGIC: &[u8] = b"SQLite format 3\0";
+const DEFAULT_ROW_LIMIT: usize = 10;
+const TABLE_ICON: char = '\u{f0ce}';
+const INDEX_ICON: char = '\u{f084}';
+
+struct Column {
+    name: String,
+    data_type: String,
+    not_null: bool,
+    primary_key: bool,
+}
+
+struct Table {
+    name: String,
+    columns: Vec<Column>,
+    indexes: Vec<String>,
+}
+
+pub fn is_sqlite_file(path: &Path) -> bool {
+    read_head(path).is_ok_and(|head| head.starts_with(SQLITE_MAGIC))
+}
+
+pub fn render_sqlite(path: &Path) -> io::Result<()> {
+    let config = get_config();
+    let connection =
+        Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(to_io_error)?;
+
+    let mut tables = read_schema(&connection).map_err(to_io_error)?;
+    if let Some(name) = &config.sqlite_table {
+        tables.retain(|table| &table.name == name);
+        if tables.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No table named {} in {}", name, path.display()),
+            ));
+        }
+    }
+
+    render_schema(path, &tables)?;
+
+    let limit = config.sqlite_limit.unwrap_or(DEFAULT_ROW_LIMIT);
+    for table in &tables {
+        render_rows(&connection, table, limit)?;
+    }
+
+    Ok(())
+}
+
+fn read_schema(connection: &Connection) -> rusqlite::Result<Vec<Table>> {
+    let mut statement = connection.prepare(
+        "SELECT name FROM sqlite_master \
+         WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )?;
+    let names = statement
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    names
+        .into_iter()
+        .map(|name| {
+            let mut columns_statement =
+                connection.prepare(&format!("PRAGMA table_info({})", quote_identifier(&name)))?;
+            let columns = columns_statement
+                .query_map([], |row| {
+                    Ok(Column {
+                        name: row.get(1)?,
+                        data_type: row.get(2)?,
+                        not_null: row.get(3)?,
+                        primary_key: row.get::<_, i64>(5)? > 0,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            let mut index_statement =
+                connection.prepare(&format!("PRAGMA index_list({})", quote_identifier(&name)))?;
+            let indexes = index_statement
+                .query_map([], |row| {
+                    let index: String = row.get(1)?;
+                    let unique: bool = row.get(2)?;
+                    Ok(if unique {
+                        format!("{} (unique)", index)
+                    } else {
+                        index
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            Ok(Table {
+                name,
+                columns,
+                indexes,
+            })
+        })
+        .collect()
+}
+
+fn render_schema(path: &Path, tables: &[Table]) -> io::Result<()> {
+    let mut stdout = get_stdout();
+    stdout.set_color(ColorSpec::new().set_fg(Some(Color::Blue)).set_bold(true))?;
+    writeln!(stdout, "{}  {}", TABLE_ICON, path.display())?;
+    stdout.reset()?;
+
+    for (i, table) in tables.iter().enumerate() {
+        let is_last_table = i == tables.len() - 1;
+        write!(
+            stdout,
+            "{}",
+            if is_last_table {
+                TREE_LAST_BRANCH
+            } else {
+                TREE_BRANCH
+            }
+        )?;
+        stdout.set_color(ColorSpec::new().set_fg(Some(Color::Blue)).set_bold(true))?;
+        writeln!(stdout, "{}  {}", TABLE_ICON, table.name)?;
+        stdout.reset()?;
+
+        let prefix = if is_last_table {
+            TREE_INDENT
+        } else {
+            TREE_VERTICAL
+        };
+        let item_count = table.columns.len() + table.indexes.len();
+        for (j, column) in table.columns.iter().enumerate() {
+            write!(
+                stdout,
+                "{}{}",
+                prefix,
+                if j == item_count - 1 {
+                    TREE_LAST_BRANCH
+                } else {
+                    TREE_BRANCH
+                }
+            )?;
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::White)))?;
+            write!(stdout, "{}", column.name)?;
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+            write!(stdout, " {}", column.data_type)?;
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
+            if column.primary_key {
+                write!(stdout, " PRIMARY KEY")?;
+            }
+            if column.not_null {
+                write!(stdout, " NOT NULL")?;
+            }
+            stdout.reset()?;
+            writeln!(stdout)?;
+        }
+        for (j, index) in table.indexes.iter().enumerate() {
+            let position = table.columns.len() + j;
+            write!(
+                stdout,
+                "{}{}",
+                prefix,
+                if position == item_count - 1 {
+                    TREE_LAST_BRANCH
+                } else {
+                    TREE_BRANCH
+                }
+            )?;
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Magenta)))?;
+            writeln!(stdout, "{}  {}", INDEX_ICON, index)?;
+            stdout.reset()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders the first `limit` rows of a table through the Markdown table
+/// renderer.
+fn render_rows(connection: &Connection, table: &Table, limit: usize) -> io::Result<()> {
+    let mut stdout = get_stdout();
+    writeln!(stdout)?;
+    stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)).set_bold(true))?;
+    writeln!(stdout, "{}:", table.name)?;
+    stdout.reset()?;
+
+    let mut statement = connection
+        .prepare(&format!(
+            "SELECT * FROM {} LIMIT {}",
+            quote_identifier(&table.name),
+            limit
+        ))
+        .map_err(to_io_error)?;
+    let column_count = statement.column_count();
+
+    let header = table_row(statement.column_names().into_iter().map(str::to_string));
+    let mut rows = vec![header];
+    let mut query = statement.query([]).map_err(to_io_error)?;
+    while let Some(row) = query.next().map_err(to_io_error)? {
+        let cells = (0..column_count).map(|i| match row.get_ref(i) {
+            Ok(value) => format_value(value),
+            Err(_) => String::new(),
+        });
+        rows.push(table_row(cells));
+    }
+
+    render_markdown(&json!({
+        "type": "root",
+        "children": [{ "type": "table", "children": rows }],
+    }))
+}
+
+fn table_row(cells: impl Iterator<Item = String>) -> Value {
+    let cells: Vec<Value> = cells
+        .map(|cell| {
+            json!({
+                "type": "tableCell",
+                "children": [{ "type": "text", "value": cell }],
+            })
+        })
+        .collect();
+    json!({ "type": "tableRow", "children": cells })
+}
+
+fn format_value(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => "NULL".to_string(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(f) => f.to_string(),
+        ValueRef::Text(text) => String::from_utf8_lossy(text).replace('\n', " "),
+        ValueRef::Blob(blob) => format!("<{} bytes>", blob.len()),
+    }
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn to_io_error(e: rusqlite::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
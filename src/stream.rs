@@ -0,0 +1,107 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::compression::{open_decompressed, Compression};
+use crate::config::get_config;
+use crate::line_range::LineSelection;
+use crate::utils::{decode_stream, detect_file_encoding, file_encoding};
+
+/// How often `--follow` checks a file for new data.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Files up to this size have their lines counted before they are shown, so
+/// the line number gutter fits exactly.
+const COUNTED_FILE_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Gutter width for input of unknown length. It widens past a million lines.
+pub const STREAMING_LINE_NUMBER_WIDTH: usize = 6;
+
+/// Reads a file like `tail -f`: at the end it waits for more data instead of
+/// stopping, and starts over when the file is truncated.
+struct FollowReader {
+    file: File,
+    path: PathBuf,
+    position: u64,
+}
+
+impl Read for FollowReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let count = self.file.read(buf)?;
+            if count > 0 {
+                self.position += count as u64;
+                return Ok(count);
+            }
+            thread::sleep(FOLLOW_INTERVAL);
+            if fs::metadata(&self.path).is_ok_and(|metadata| metadata.len() < self.position) {
+                self.position = self.file.seek(SeekFrom::Start(0))?;
+            }
+        }
+    }
+}
+
+/// Opens a text file to be read line by line as UTF-8, whatever its
+/// encoding. An encoding already detected for the filename header is reused.
+/// With `--follow` the stream never ends.
+pub fn open_text_stream(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let encoding = match file_encoding(path) {
+        Some(encoding) => encoding,
+        None => detect_file_encoding(path)?,
+    };
+    let reader: Box<dyn Read> = if get_config().follow && Compression::detect(path).is_none() {
+        Box::new(FollowReader {
+            file: File::open(path)?,
+            path: path.to_path_buf(),
+            position: 0,
+        })
+    } else {
+        open_decompressed(path)?
+    };
+    Ok(Box::new(BufReader::new(decode_stream(reader, encoding))))
+}
+
+/// Copies a text file to standard output as UTF-8 without holding it in
+/// memory, for when the output is piped.
+pub fn copy_text_file(path: &Path) -> io::Result<()> {
+    let mut reader = open_text_stream(path)?;
+    let mut stdout = io::stdout().lock();
+    if !LineSelection::is_active() {
+        io::copy(&mut reader, &mut stdout)?;
+        return Ok(());
+    }
+
+    let selection = LineSelection::current();
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        if selection.is_past(line_number) {
+            break;
+        }
+        if selection.shows(line_number) {
+            writeln!(stdout, "{}", line?)?;
+        }
+    }
+    Ok(())
+}
+
+/// The width of the line number gutter for a file. Small files are counted;
+/// large, compressed and followed files get room for a million lines.
+pub fn line_number_width(path: &Path) -> io::Result<usize> {
+    if get_config().follow
+        || fs::metadata(path)?.len() > COUNTED_FILE_SIZE
+        || Compression::detect(path).is_some()
+    {
+        return Ok(STREAMING_LINE_NUMBER_WIDTH);
+    }
+    let content = fs::read(path)?;
+    let mut line_count = content.iter().filter(|&&byte| byte == b'\n').count();
+    if content.last().is_some_and(|&byte| byte != b'\n') {
+        line_count += 1;
+    }
+    Ok(line_count.to_string().len())
+}
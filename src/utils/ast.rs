@@ -46,6 +46,21 @@ pub fn process_footnotes(node: &Value) {
     }
 }
 
+/// Collects the URLs of all images in the document.
+pub fn collect_image_urls(node: &Value, urls: &mut Vec<String>) {
+    if let Some("image") = node["type"].as_str() {
+        if let Some(url) = node["url"].as_str() {
+            urls.push(url.to_string());
+        }
+    }
+
+    if let Some(children) = node["children"].as_array() {
+        for child in children {
+            collect_image_urls(child, urls);
+        }
+    }
+}
+
 pub fn modify_heading_ast(node: &mut Value) {
     if node["type"] == "heading" {
         if let Some(children) = node["children"].as_array_mut() {
@@ -0,0 +1,224 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Decoder, Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::compression::open_decompressed;
+use crate::config::get_config;
+use crate::utils::SNIFF_LEN;
+
+lazy_static! {
+    /// The encoding each text file was read in, for the filename header.
+    static ref FILE_ENCODINGS: Mutex<HashMap<PathBuf, &'static Encoding>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Reads a text file in whatever encoding it uses and converts it to UTF-8.
+pub fn read_text_file(path: &Path) -> io::Result<String> {
+    let mut bytes = Vec::new();
+    open_decompressed(path)?.read_to_end(&mut bytes)?;
+    let (text, encoding) = decode_text(&bytes);
+    FILE_ENCODINGS
+        .lock()
+        .unwrap()
+        .insert(path.to_path_buf(), encoding);
+    Ok(text)
+}
+
+/// Detects the encoding of a text file from its first few kilobytes, for
+/// files that are read as a stream.
+pub fn detect_file_encoding(path: &Path) -> io::Result<&'static Encoding> {
+    let mut head = Vec::new();
+    open_decompressed(path)?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut head)?;
+    let encoding = detect_encoding(&head);
+    FILE_ENCODINGS
+        .lock()
+        .unwrap()
+        .insert(path.to_path_buf(), encoding);
+    Ok(encoding)
+}
+
+/// Converts text to UTF-8 as it is read, in the encoding detected from its
+/// start. When that start merely looked like UTF-8, the rest is checked as it
+/// passes and switches to a legacy encoding at the first bytes that are not
+/// UTF-8, much as `read_text_file` would have guessed from the whole file.
+pub fn decode_stream<R: Read + 'static>(reader: R, encoding: &'static Encoding) -> Box<dyn Read> {
+    if encoding == UTF_8 && get_config().encoding.is_none() {
+        return Box::new(Utf8OrLegacyReader::new(reader));
+    }
+    Box::new(
+        DecodeReaderBytesBuilder::new()
+            .encoding(Some(encoding))
+            .strip_bom(true)
+            .build(reader),
+    )
+}
+
+/// Passes UTF-8 through until the first byte sequence that is not UTF-8, and
+/// decodes everything from there in the legacy encoding that the text read
+/// so far suggests.
+struct Utf8OrLegacyReader<R> {
+    inner: R,
+    detector: EncodingDetector,
+    /// Set once the text turned out not to be UTF-8.
+    decoder: Option<Decoder>,
+    /// The start of a character split across reads.
+    pending: Vec<u8>,
+    output: Vec<u8>,
+    position: usize,
+    started: bool,
+}
+
+impl<R: Read> Utf8OrLegacyReader<R> {
+    fn new(inner: R) -> Self {
+        Utf8OrLegacyReader {
+            inner,
+            detector: EncodingDetector::new(),
+            decoder: None,
+            pending: Vec::new(),
+            output: Vec::new(),
+            position: 0,
+            started: false,
+        }
+    }
+
+    fn convert(&mut self, chunk: &[u8], last: bool) {
+        if let Some(decoder) = &mut self.decoder {
+            decode_into(decoder, chunk, last, &mut self.output);
+            return;
+        }
+
+        self.pending.extend_from_slice(chunk);
+        let (valid_up_to, is_utf8) = match std::str::from_utf8(&self.pending) {
+            Ok(_) => (self.pending.len(), true),
+            // A character split across reads is completed by the next one
+            Err(e) if e.error_len().is_none() && !last => (e.valid_up_to(), true),
+            Err(e) => (e.valid_up_to(), false),
+        };
+        let rest = self.pending.split_off(valid_up_to);
+        self.detector.feed(&self.pending, false);
+        self.output.append(&mut self.pending);
+        self.pending = rest;
+
+        if !is_utf8 {
+            self.detector.feed(&self.pending, true);
+            let encoding = match self.detector.guess(None, true) {
+                encoding if encoding == UTF_8 => WINDOWS_1252,
+                encoding => encoding,
+            };
+            let mut decoder = encoding.new_decoder_without_bom_handling();
+            let rest = std::mem::take(&mut self.pending);
+            decode_into(&mut decoder, &rest, last, &mut self.output);
+            self.decoder = Some(decoder);
+        }
+    }
+}
+
+impl<R: Read> Read for Utf8OrLegacyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.output.len() {
+            self.output.clear();
+            self.position = 0;
+            let mut chunk = [0; 8192];
+            let count = self.inner.read(&mut chunk)?;
+            self.convert(&chunk[..count], count == 0);
+            if !self.started && self.output.len() >= 3 {
+                self.started = true;
+                if self.output.starts_with(b"\xef\xbb\xbf") {
+                    self.position = 3;
+                }
+            }
+            if count == 0 && self.position == self.output.len() {
+                return Ok(0);
+            }
+        }
+        let count = buf.len().min(self.output.len() - self.position);
+        buf[..count].copy_from_slice(&self.output[self.position..self.position + count]);
+        self.position += count;
+        Ok(count)
+    }
+}
+
+fn decode_into(decoder: &mut Decoder, bytes: &[u8], last: bool, output: &mut Vec<u8>) {
+    let capacity = decoder
+        .max_utf8_buffer_length(bytes.len())
+        .unwrap_or(bytes.len() * 3 + 16);
+    let mut text = String::with_capacity(capacity);
+    let _ = decoder.decode_to_string(bytes, &mut text, last);
+    output.extend_from_slice(text.as_bytes());
+}
+
+/// The encoding a file was decoded from by `read_text_file` or
+/// `detect_file_encoding`.
+pub fn file_encoding(path: &Path) -> Option<&'static Encoding> {
+    FILE_ENCODINGS.lock().unwrap().get(path).copied()
+}
+
+/// Converts text to UTF-8, returning the encoding it was in. Bytes that are
+/// invalid in that encoding become replacement characters.
+pub fn decode_text(bytes: &[u8]) -> (String, &'static Encoding) {
+    let encoding = detect_encoding(bytes);
+    let (text, _) = encoding.decode_with_bom_removal(bytes);
+    (text.into_owned(), encoding)
+}
+
+/// Picks the encoding set with `--encoding`, or else the one named by a byte
+/// order mark, UTF-16 recognised by its zero bytes, UTF-8 when the text is
+/// valid UTF-8, and finally the best guess among legacy encodings such as
+/// Windows-1252 or Shift_JIS.
+pub fn detect_encoding(bytes: &[u8]) -> &'static Encoding {
+    if let Some(encoding) = get_config()
+        .encoding
+        .as_deref()
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+    {
+        return encoding;
+    }
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    if let Some(encoding) = utf16_without_bom(bytes) {
+        return encoding;
+    }
+    match std::str::from_utf8(bytes) {
+        // A sample may end in the middle of a character
+        Ok(_) => return UTF_8,
+        Err(e) if e.error_len().is_none() => return UTF_8,
+        Err(_) => {}
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+/// Recognises mostly-ASCII UTF-16 without a byte order mark, where every
+/// other byte is zero.
+pub fn utf16_without_bom(bytes: &[u8]) -> Option<&'static Encoding> {
+    let pairs = bytes.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let zeros_at = |offset: usize| {
+        bytes
+            .chunks_exact(2)
+            .filter(|pair| pair[offset] == 0)
+            .count()
+    };
+    let (even_zeros, odd_zeros) = (zeros_at(0), zeros_at(1));
+    let mostly = |zeros: usize| zeros * 10 >= pairs * 4;
+    let hardly = |zeros: usize| zeros * 20 <= pairs;
+    if mostly(odd_zeros) && hardly(even_zeros) {
+        Some(UTF_16LE)
+    } else if mostly(even_zeros) && hardly(odd_zeros) {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,92 @@
+use encoding_rs::{Encoding, UTF_16LE};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::utils::encoding::utf16_without_bom;
+
+pub const SNIFF_LEN: usize = 8192;
+
+/// Reads up to the first few kilobytes of a file for content sniffing.
+pub fn read_head(path: &Path) -> io::Result<Vec<u8>> {
+    let mut buffer = Vec::with_capacity(SNIFF_LEN);
+    File::open(path)?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+/// Heuristically decides whether a file holds binary rather than text data.
+pub fn is_binary_file(path: &Path) -> bool {
+    match read_head(path) {
+        Ok(head) => is_binary(&head),
+        Err(_) => false,
+    }
+}
+
+pub fn is_binary(head: &[u8]) -> bool {
+    if Encoding::for_bom(head).is_some() {
+        return false;
+    }
+    if utf16_without_bom(head).is_some_and(|encoding| is_utf16_text(head, encoding)) {
+        return false;
+    }
+    if head.contains(&0) {
+        return true;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => false,
+        // A multi-byte character cut off at the end of the sample is still text
+        Err(e) if e.error_len().is_none() => false,
+        // Text in a legacy encoding like Latin-1 or Shift_JIS is not valid
+        // UTF-8 either, but unlike binary data it has few control characters
+        Err(_) => {
+            let controls = head
+                .iter()
+                .filter(|&&byte| {
+                    (byte < 0x20 && !matches!(byte, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b))
+                        || byte == 0x7f
+                })
+                .count();
+            controls * 50 > head.len()
+        }
+    }
+}
+
+/// Tables of 16-bit numbers have as many zero bytes as UTF-16 text, so the
+/// sample only counts as text when it decodes cleanly to printable text.
+fn is_utf16_text(head: &[u8], encoding: &'static Encoding) -> bool {
+    let mut head = &head[..head.len() & !1];
+    // The sample may end between the two halves of a surrogate pair
+    let last_unit = match head {
+        [.., first, second] if encoding == UTF_16LE => Some(u16::from_le_bytes([*first, *second])),
+        [.., first, second] => Some(u16::from_be_bytes([*first, *second])),
+        _ => None,
+    };
+    if last_unit.is_some_and(|unit| (0xd800..0xdc00).contains(&unit)) {
+        head = &head[..head.len() - 2];
+    }
+    encoding
+        .decode_without_bom_handling_and_without_replacement(head)
+        .is_some_and(|text| {
+            !text
+                .chars()
+                .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c' | '\x1b'))
+        })
+}
+
+/// Formats a byte count with a binary unit suffix, like `ls -h`.
+pub fn format_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{}{}", size, UNITS[0])
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}
@@ -11,33 +11,63 @@ use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 use crate::utils::theme::create_theme;
 
 pub fn highlight_code<W: Write>(code: &str, lang: &str, writer: &mut W) -> io::Result<()> {
+    highlight_code_with_background(code, lang, writer, None)
+}
+
+/// Highlights code on a background colour that fills the rest of the line.
+#[allow(clippy::io_other_error)]
+pub fn highlight_code_with_background<W: Write>(
+    code: &str,
+    lang: &str,
+    writer: &mut W,
+    background: Option<Color>,
+) -> io::Result<()> {
     let mut highlighter = Highlighter::new();
     let language = Language::from_token(lang).unwrap_or(Language::Plaintext);
     let theme = create_theme();
-    let formatter = TerminalFormatter::new(theme);
+    let formatter = TerminalFormatter::new(theme, background);
 
+    formatter.set_background()?;
     for line in code.lines() {
         highlighter
             .highlight_to_writer(language, &formatter, line, writer)
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
     }
+    if background.is_some() {
+        let mut stdout = formatter.stdout.borrow_mut();
+        // Erasing to the end of the line paints it with the background
+        write!(stdout, "\x1b[K")?;
+        stdout.reset()?;
+        stdout.flush()?;
+    }
 
     Ok(())
 }
 
 struct TerminalFormatter {
     theme: Theme,
+    background: Option<Color>,
     stdout: RefCell<StandardStream>,
 }
 
 impl TerminalFormatter {
-    fn new(theme: Theme) -> Self {
+    fn new(theme: Theme, background: Option<Color>) -> Self {
         Self {
             theme,
+            background,
             stdout: RefCell::new(StandardStream::stdout(ColorChoice::Always)),
         }
     }
 
+    fn set_background(&self) -> io::Result<()> {
+        if self.background.is_some() {
+            self.stdout
+                .borrow_mut()
+                .set_color(ColorSpec::new().set_bg(self.background))?;
+        }
+        Ok(())
+    }
+
     fn color_from_hex(&self, hex: &str) -> Color {
         let rgb = color_from_hex(hex).unwrap_or((255, 255, 255));
         Color::Rgb(rgb.0, rgb.1, rgb.2)
@@ -62,10 +92,11 @@ impl Formatter for TerminalFormatter {
                 let color = self.color_from_hex(&style.primary_color);
                 self.stdout
                     .borrow_mut()
-                    .set_color(ColorSpec::new().set_fg(Some(color)))?;
+                    .set_color(ColorSpec::new().set_fg(Some(color)).set_bg(self.background))?;
             }
             HighlightEvent::HighlightEnd => {
                 self.stdout.borrow_mut().reset()?;
+                self.set_background()?;
             }
         }
         Ok(())
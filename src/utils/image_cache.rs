@@ -0,0 +1,520 @@
+use lazy_static::lazy_static;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::redirect::Policy;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::config::get_config;
+use crate::constants::IMAGE_FOLDER;
+use crate::utils::{format_size, looks_like_svg};
+
+/// Cache size in megabytes when `image_cache_size` is not set.
+const DEFAULT_IMAGE_CACHE_SIZE_MB: u64 = 100;
+const DEFAULT_IMAGE_MAX_SIZE_MB: u64 = 20;
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 5;
+const DEFAULT_READ_TIMEOUT_SECS: u64 = 15;
+const MAX_REDIRECTS: usize = 5;
+const MAX_PARALLEL_DOWNLOADS: usize = 8;
+
+/// The outcome of a download. Errors keep their kind and message, since
+/// `io::Error` cannot be cloned.
+type Download = Result<PathBuf, (io::ErrorKind, String)>;
+
+lazy_static! {
+    /// Downloads made during this run, by URL.
+    static ref DOWNLOADS: Mutex<HashMap<String, Download>> = Mutex::new(HashMap::new());
+}
+
+static PREFETCHING: AtomicBool = AtomicBool::new(false);
+
+/// How long a download may take and how large the image may be.
+pub struct DownloadLimits {
+    pub connect_timeout: Duration,
+    pub timeout: Duration,
+    pub max_size: u64,
+}
+
+impl DownloadLimits {
+    /// The limits for images, from the `image_*` options.
+    pub fn configured() -> Self {
+        let config = get_config();
+        DownloadLimits {
+            connect_timeout: Duration::from_secs(
+                config
+                    .image_connect_timeout
+                    .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS),
+            ),
+            timeout: Duration::from_secs(config.image_timeout.unwrap_or(DEFAULT_READ_TIMEOUT_SECS)),
+            max_size: config.image_max_size.unwrap_or(DEFAULT_IMAGE_MAX_SIZE_MB) * 1024 * 1024,
+        }
+    }
+}
+
+/// What is known about a cached image, stored next to it as `<key>.json`.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    url: String,
+    file_name: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// The persistent image cache, e.g. `~/.cache/see/images` on Linux.
+pub fn image_cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("see").join("images"))
+}
+
+/// Returns a local copy of a remote image. Cached copies are revalidated with
+/// the server and reused while unchanged, or when the server cannot be
+/// reached. With `--offline` nothing is downloaded. Each URL is only fetched
+/// once per run, so images prefetched with `prefetch_images` are reused.
+pub fn download_image(url: &str) -> io::Result<PathBuf> {
+    if let Some(result) = DOWNLOADS.lock().unwrap().get(url) {
+        return result
+            .clone()
+            .map_err(|(kind, message)| io::Error::new(kind, message));
+    }
+
+    let result = fetch_image(url);
+    DOWNLOADS.lock().unwrap().insert(
+        url.to_string(),
+        result
+            .as_ref()
+            .map(PathBuf::clone)
+            .map_err(|e| (e.kind(), e.to_string())),
+    );
+    if result.is_ok() && !PREFETCHING.load(Ordering::Relaxed) {
+        evict_cache();
+    }
+    result
+}
+
+/// Downloads the given images in parallel ahead of rendering, so a document
+/// with many images only waits about as long as the slowest one.
+pub fn prefetch_images(urls: &[String]) {
+    let mut pending: Vec<&String> = {
+        let downloads = DOWNLOADS.lock().unwrap();
+        urls.iter()
+            .filter(|url| !downloads.contains_key(url.as_str()))
+            .collect()
+    };
+    pending.sort();
+    pending.dedup();
+
+    // Evicting while other downloads run could delete what they just fetched
+    PREFETCHING.store(true, Ordering::Relaxed);
+    for batch in pending.chunks(MAX_PARALLEL_DOWNLOADS) {
+        thread::scope(|scope| {
+            for url in batch {
+                scope.spawn(|| download_image(url));
+            }
+        });
+    }
+    PREFETCHING.store(false, Ordering::Relaxed);
+    evict_cache();
+}
+
+fn fetch_image(url: &str) -> io::Result<PathBuf> {
+    let config = get_config();
+    let dir = match image_cache_dir() {
+        Some(dir) if fs::create_dir_all(&dir).is_ok() => dir,
+        // Without a cache directory images only live as long as the process
+        _ => PathBuf::from(IMAGE_FOLDER.get().expect("Image folder not set")),
+    };
+    let key = cache_key(url);
+    let cached = read_entry(&dir, &key)
+        .filter(|entry| entry.url == url && dir.join(&entry.file_name).exists());
+
+    if config.offline {
+        return match cached {
+            Some(entry) => Ok(use_entry(&dir, &entry)),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "offline and not cached",
+            )),
+        };
+    }
+
+    let mut request = http_client()?.get(url);
+    if let Some(entry) = &cached {
+        if let Some(etag) = &entry.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &entry.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = match request.send() {
+        Ok(response) => response,
+        Err(e) => {
+            return match cached {
+                Some(entry) => Ok(use_entry(&dir, &entry)),
+                None => Err(request_error(e)),
+            }
+        }
+    };
+    if response.status() == StatusCode::NOT_MODIFIED {
+        if let Some(entry) = cached {
+            return Ok(use_entry(&dir, &entry));
+        }
+    }
+    if !response.status().is_success() {
+        return Err(io::Error::other(format!("HTTP {}", response.status())));
+    }
+
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from)
+    };
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+    let (content, mime) = read_image_body(response, DownloadLimits::configured().max_size)?;
+
+    let extension = image_extension(mime.as_deref(), &content, url)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not an image"))?;
+
+    // A changed image may come back in another format
+    if let Some(entry) = &cached {
+        let _ = fs::remove_file(dir.join(&entry.file_name));
+    }
+    let entry = CacheEntry {
+        url: url.to_string(),
+        file_name: format!("{}.{}", key, extension),
+        etag,
+        last_modified,
+    };
+    let path = dir.join(&entry.file_name);
+    write_atomically(&path, &content)?;
+    write_atomically(
+        &dir.join(format!("{}.json", key)),
+        &serde_json::to_vec(&entry)?,
+    )?;
+    Ok(path)
+}
+
+/// Reads the body of a response, returning it with its media type. Pages
+/// that are not images and bodies over `max_size` bytes are rejected.
+fn read_image_body(response: Response, max_size: u64) -> io::Result<(Vec<u8>, Option<String>)> {
+    let mime = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|mime| mime.trim().to_lowercase());
+    // Links to an image's web page, like GitHub's blob view, return HTML
+    if let Some(mime) = mime.as_deref() {
+        if !mime.starts_with("image/") && mime != "application/octet-stream" {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("not an image ({})", mime),
+            ));
+        }
+    }
+
+    Ok((read_body(response, max_size)?, mime))
+}
+
+/// Reads the body of a response, rejecting bodies over `max_size` bytes.
+pub fn read_body(response: Response, max_size: u64) -> io::Result<Vec<u8>> {
+    let too_large = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("larger than {}", format_size(max_size)),
+        )
+    };
+    if response
+        .content_length()
+        .is_some_and(|length| length > max_size)
+    {
+        return Err(too_large());
+    }
+    // The announced length can be missing or wrong, so the body is capped too
+    let mut content = Vec::new();
+    response
+        .take(max_size + 1)
+        .read_to_end(&mut content)
+        .map_err(read_error)?;
+    if content.len() as u64 > max_size {
+        return Err(too_large());
+    }
+    Ok(content)
+}
+
+/// A client that gives up on slow servers instead of stalling the output.
+fn http_client() -> io::Result<Client> {
+    client_with_limits(&DownloadLimits::configured())
+}
+
+pub fn client_with_limits(limits: &DownloadLimits) -> io::Result<Client> {
+    Client::builder()
+        .connect_timeout(limits.connect_timeout)
+        .timeout(limits.timeout)
+        .redirect(Policy::limited(MAX_REDIRECTS))
+        .build()
+        .map_err(io::Error::other)
+}
+
+/// Short descriptions, since they end up in the image placeholder.
+fn request_error(e: reqwest::Error) -> io::Error {
+    if e.is_timeout() {
+        io::Error::new(io::ErrorKind::TimedOut, "timeout")
+    } else if e.is_connect() {
+        io::Error::new(io::ErrorKind::ConnectionRefused, "could not connect")
+    } else if e.is_redirect() {
+        io::Error::other("too many redirects")
+    } else {
+        io::Error::other(e)
+    }
+}
+
+fn read_error(e: io::Error) -> io::Error {
+    match e
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<reqwest::Error>())
+    {
+        Some(inner) if inner.is_timeout() => io::Error::new(io::ErrorKind::TimedOut, "timeout"),
+        _ if e.kind() == io::ErrorKind::TimedOut => {
+            io::Error::new(io::ErrorKind::TimedOut, "timeout")
+        }
+        _ => e,
+    }
+}
+
+/// Deletes the image cache, returning how many images it held and their
+/// total size.
+pub fn clear_image_cache() -> io::Result<(usize, u64)> {
+    let Some(dir) = image_cache_dir().filter(|dir| dir.exists()) else {
+        return Ok((0, 0));
+    };
+    let images = cached_images(&dir)?;
+    let size = images.iter().map(|(_, size, _)| size).sum();
+    fs::remove_dir_all(&dir)?;
+    Ok((images.len(), size))
+}
+
+fn cache_key(url: &str) -> String {
+    format!("{:x}", Sha256::digest(url.as_bytes()))
+}
+
+/// Writes a cache file through a temporary file that is renamed into place,
+/// so other `see` processes never read it half-written.
+fn write_atomically(path: &Path, content: &[u8]) -> io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    file.write_all(content)?;
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+fn read_entry(dir: &Path, key: &str) -> Option<CacheEntry> {
+    let json = fs::read(dir.join(format!("{}.json", key))).ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
+/// Marks a cached image as used just now, which keeps it from eviction the
+/// longest.
+fn use_entry(dir: &Path, entry: &CacheEntry) -> PathBuf {
+    let path = dir.join(&entry.file_name);
+    if let Ok(file) = File::options().append(true).open(&path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+    path
+}
+
+/// Every cached image with its last use and size, least recently used first.
+fn cached_images(dir: &Path) -> io::Result<Vec<(SystemTime, u64, PathBuf)>> {
+    let mut images: Vec<_> = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension != "json")
+        })
+        .filter_map(|path| {
+            let metadata = fs::metadata(&path).ok()?;
+            Some((metadata.modified().ok()?, metadata.len(), path))
+        })
+        .collect();
+    images.sort();
+    Ok(images)
+}
+
+/// Removes the least recently used images until the cache fits in
+/// `image_cache_size`, always keeping the images downloaded during this run.
+fn evict_cache() {
+    let Some(dir) = image_cache_dir() else {
+        return;
+    };
+    let Ok(images) = cached_images(&dir) else {
+        return;
+    };
+    let max_size = get_config()
+        .image_cache_size
+        .unwrap_or(DEFAULT_IMAGE_CACHE_SIZE_MB)
+        * 1024
+        * 1024;
+    let downloads = DOWNLOADS.lock().unwrap();
+    let keep: Vec<&PathBuf> = downloads
+        .values()
+        .filter_map(|result| result.as_ref().ok())
+        .collect();
+
+    let mut total: u64 = images.iter().map(|(_, size, _)| size).sum();
+    for (_, size, path) in images {
+        if total <= max_size {
+            break;
+        }
+        if keep.contains(&&path) {
+            continue;
+        }
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(path.with_extension("json"));
+        total -= size;
+    }
+}
+
+/// Picks a file extension from the image data, the content type or the URL,
+/// in that order, since servers often send a generic content type. Returns
+/// `None` when the data is not an image at all.
+fn image_extension(mime: Option<&str>, content: &[u8], url: &str) -> Option<String> {
+    if let Some(extension) = image::guess_format(content)
+        .ok()
+        .and_then(|format| format.extensions_str().first())
+    {
+        return Some(extension.to_string());
+    }
+    if looks_like_svg(content) {
+        return Some("svg".to_string());
+    }
+
+    // Formats the image crate does not know, which viuer cannot show either,
+    // are still cached under their own extension
+    let extension = match mime {
+        Some("image/x-icon" | "image/vnd.microsoft.icon") => "ico",
+        Some("image/tiff") => "tiff",
+        Some("image/avif") => "avif",
+        Some("image/heic") => "heic",
+        Some(mime) if mime.starts_with("image/") => {
+            return url::Url::parse(url)
+                .ok()
+                .and_then(|url| {
+                    Path::new(url.path())
+                        .extension()
+                        .and_then(|extension| extension.to_str())
+                        .filter(|extension| extension.chars().all(|c| c.is_ascii_alphanumeric()))
+                        .map(str::to_lowercase)
+                })
+                .or_else(|| Some("img".to_string()));
+        }
+        _ => return None,
+    };
+    Some(extension.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    /// Answers a single request with `response`, or keeps the connection open
+    /// without answering when it is `None`. Returns the URL to request.
+    fn serve_once(response: Option<Vec<u8>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/image.png", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(&stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|count| count > 2) {
+                line.clear();
+            }
+            match response {
+                Some(response) => {
+                    let _ = (&stream).write_all(&response);
+                }
+                None => thread::sleep(Duration::from_secs(5)),
+            }
+        });
+        url
+    }
+
+    fn response(content_type: &str, content_length: Option<usize>, body: &[u8]) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nConnection: close\r\n",
+            content_type
+        );
+        if let Some(length) = content_length {
+            response.push_str(&format!("Content-Length: {}\r\n", length));
+        }
+        response.push_str("\r\n");
+        let mut response = response.into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    fn download(url: &str) -> io::Result<Vec<u8>> {
+        let limits = DownloadLimits {
+            connect_timeout: Duration::from_secs(1),
+            timeout: Duration::from_secs(1),
+            max_size: 1024,
+        };
+        let response = client_with_limits(&limits)?
+            .get(url)
+            .send()
+            .map_err(request_error)?;
+        read_image_body(response, limits.max_size).map(|(content, _)| content)
+    }
+
+    #[test]
+    fn accepts_small_images() {
+        let url = serve_once(Some(response("image/png", Some(4), b"\x89PNG")));
+        assert_eq!(download(&url).unwrap(), b"\x89PNG");
+    }
+
+    #[test]
+    fn gives_up_on_servers_that_do_not_answer() {
+        let url = serve_once(None);
+        let error = download(&url).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn rejects_images_announced_as_too_large() {
+        let url = serve_once(Some(response("image/png", Some(2048), &[0; 2048])));
+        let error = download(&url).unwrap_err();
+        assert_eq!(error.to_string(), "larger than 1.0K");
+    }
+
+    #[test]
+    fn caps_bodies_without_a_length() {
+        let url = serve_once(Some(response("image/png", None, &[0; 2048])));
+        let error = download(&url).unwrap_err();
+        assert_eq!(error.to_string(), "larger than 1.0K");
+    }
+
+    #[test]
+    fn rejects_pages_that_are_not_images() {
+        let url = serve_once(Some(response(
+            "text/html; charset=utf-8",
+            Some(6),
+            b"<html>",
+        )));
+        let error = download(&url).unwrap_err();
+        assert_eq!(error.to_string(), "not an image (text/html)");
+    }
+}
@@ -1,37 +1,106 @@
-use crate::constants::IMAGE_FOLDER;
-use reqwest::blocking::Client;
-use sha2::{Digest, Sha256};
-use std::fs;
-use std::io;
-use std::path::{Path, PathBuf};
-
-pub fn download_image(url: &str) -> io::Result<PathBuf> {
-    let client = Client::new();
-    let response = client
-        .get(url)
-        .send()
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-
-    if !response.status().is_success() {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("Failed to download image: HTTP {}", response.status()),
-        ));
+use crate::utils::read_head;
+use exif::{In, Tag};
+use image::ImageFormat;
+use resvg::{tiny_skia, usvg};
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::Path;
+
+/// Pixels per terminal cell that SVGs and Sixel images are rasterized at, so
+/// they stay sharp on terminals with graphics protocols. Half-block output
+/// scales them down.
+pub const CELL_WIDTH_PX: u32 = 10;
+pub const CELL_HEIGHT_PX: u32 = 20;
+
+/// SVGs are recognised by their content too, since downloaded images and
+/// embedded data URLs have no meaningful extension.
+pub fn is_svg(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .unwrap_or("")
+        .to_lowercase();
+    if extension == "svg" {
+        return true;
     }
 
-    let content = response
-        .bytes()
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    read_head(path).is_ok_and(|head| looks_like_svg(&head))
+}
+
+pub fn looks_like_svg(head: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(head);
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    head.starts_with("<svg")
+        || ((head.starts_with("<?xml") || head.starts_with("<!")) && head.contains("<svg"))
+}
+
+/// Rasterizes an SVG to a PNG that fits within `max_width` x `max_height`
+/// terminal cells, returning it with the number of columns it fills.
+pub fn rasterize_svg(path: &Path, max_width: u32, max_height: u32) -> io::Result<(Vec<u8>, u32)> {
+    let data = fs::read(path)?;
+    let mut options = usvg::Options {
+        resources_dir: path.parent().map(Path::to_path_buf),
+        ..Default::default()
+    };
+    options.fontdb_mut().load_system_fonts();
+    let tree = usvg::Tree::from_data(&data, &options)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let size = tree.size();
+    let scale = ((max_width * CELL_WIDTH_PX) as f32 / size.width())
+        .min((max_height * CELL_HEIGHT_PX) as f32 / size.height());
+    let width = (size.width() * scale).round().max(1.0) as u32;
+    let height = (size.height() * scale).round().max(1.0) as u32;
 
-    // Generate a filename based on the hash of the content
-    let mut hasher = Sha256::new();
-    hasher.update(&content);
-    let hash = hasher.finalize();
-    let filename = format!("{:x}.jpg", hash); // Assuming JPG, adjust as needed
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid SVG size"))?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    let png = pixmap.encode_png().map_err(io::Error::other)?;
+    Ok((png, width.div_ceil(CELL_WIDTH_PX)))
+}
+
+/// Reads the EXIF block of a JPEG, TIFF, HEIF, PNG or WebP image.
+pub fn read_exif(path: &Path) -> Option<exif::Exif> {
+    let file = File::open(path).ok()?;
+    exif::Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()
+}
+
+/// The EXIF orientation of an image, from 1 (upright) to 8.
+pub fn exif_orientation(path: &Path) -> Option<u32> {
+    read_exif(path)?
+        .get_field(Tag::Orientation, In::PRIMARY)?
+        .value
+        .get_uint(0)
+}
 
-    let image_folder = IMAGE_FOLDER.get().expect("Image folder not set");
-    let path = Path::new(image_folder).join(filename);
-    fs::write(&path, content)?;
+/// Turns a photo upright according to its EXIF orientation, returning it as a
+/// PNG, or `None` when it is already stored upright.
+pub fn orient_image(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    let orientation = match exif_orientation(path) {
+        Some(orientation @ 2..=8) => orientation,
+        _ => return Ok(None),
+    };
+    let image = image::open(path).map_err(io::Error::other)?;
+    let image = match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        _ => image.rotate270(),
+    };
 
-    Ok(path)
+    let mut png = Vec::new();
+    image
+        .write_to(&mut io::Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(io::Error::other)?;
+    Ok(Some(png))
 }
@@ -0,0 +1,240 @@
+//! Builders for the mdast `serde_json::Value` nodes consumed by
+//! `render_markdown`, shared by the converters for non-Markdown documents.
+
+use serde_json::{json, Value};
+
+/// Stands for the space between inline nodes. The renderer prints the words
+/// of a text node without the whitespace around them, so the separator is a
+/// blank character that is not whitespace.
+const WORD_SEPARATOR: &str = "\u{2800}";
+
+/// The renderer trims text nodes, so the whitespace around each one is split
+/// off into a separator node of its own.
+pub fn root(children: Vec<Value>) -> Value {
+    let mut root = json!({ "type": "root", "children": children });
+    separate_words(&mut root);
+    root
+}
+
+fn separate_words(node: &mut Value) {
+    let is_block = matches!(
+        node["type"].as_str(),
+        Some("paragraph" | "heading" | "tableCell")
+    );
+    let Some(children) = node["children"].as_array_mut() else {
+        return;
+    };
+
+    let mut separated: Vec<Value> = Vec::with_capacity(children.len());
+    for mut child in children.drain(..) {
+        separate_words(&mut child);
+        let value = match (child["type"].as_str(), child["value"].as_str()) {
+            (Some("text"), Some(value)) => value.to_string(),
+            _ => {
+                // Inline code is already padded with spaces
+                if child["type"] == "inlineCode" && separated.last().is_some_and(is_separator) {
+                    separated.pop();
+                }
+                separated.push(child);
+                continue;
+            }
+        };
+        let trimmed = value.trim();
+        if value.starts_with(char::is_whitespace) {
+            push_separator(&mut separated);
+        }
+        if !trimmed.is_empty() {
+            separated.push(text(trimmed));
+            if value.ends_with(char::is_whitespace) {
+                push_separator(&mut separated);
+            }
+        }
+    }
+    if is_block {
+        if separated.first().is_some_and(is_separator) {
+            separated.remove(0);
+        }
+        if separated.last().is_some_and(is_separator) {
+            separated.pop();
+        }
+    }
+    *children = separated;
+}
+
+fn push_separator(nodes: &mut Vec<Value>) {
+    let follows_space = nodes
+        .last()
+        .is_some_and(|node| is_separator(node) || node["type"] == "inlineCode");
+    if !follows_space {
+        nodes.push(text(WORD_SEPARATOR));
+    }
+}
+
+fn is_separator(node: &Value) -> bool {
+    node["type"] == "text" && node["value"] == WORD_SEPARATOR
+}
+
+pub fn heading(depth: usize, children: Vec<Value>) -> Value {
+    json!({ "type": "heading", "depth": depth.clamp(1, 6), "children": children })
+}
+
+pub fn paragraph(children: Vec<Value>) -> Value {
+    json!({ "type": "paragraph", "children": children })
+}
+
+pub fn text(value: &str) -> Value {
+    json!({ "type": "text", "value": value })
+}
+
+pub fn strong(children: Vec<Value>) -> Value {
+    json!({ "type": "strong", "children": children })
+}
+
+pub fn emphasis(children: Vec<Value>) -> Value {
+    json!({ "type": "emphasis", "children": children })
+}
+
+pub fn delete(children: Vec<Value>) -> Value {
+    json!({ "type": "delete", "children": children })
+}
+
+pub fn inline_code(value: &str) -> Value {
+    json!({ "type": "inlineCode", "value": value })
+}
+
+pub fn link(url: &str, children: Vec<Value>) -> Value {
+    json!({ "type": "link", "url": url, "children": children })
+}
+
+pub fn image(url: &str, alt: &str) -> Value {
+    json!({ "type": "image", "url": url, "alt": alt })
+}
+
+pub fn blockquote(children: Vec<Value>) -> Value {
+    json!({ "type": "blockquote", "children": children })
+}
+
+pub fn code(lang: Option<&str>, value: &str) -> Value {
+    json!({ "type": "code", "lang": lang, "value": value })
+}
+
+pub fn list(ordered: bool, items: Vec<Value>) -> Value {
+    json!({ "type": "list", "ordered": ordered, "children": items })
+}
+
+pub fn list_item(checked: Option<bool>, children: Vec<Value>) -> Value {
+    json!({ "type": "listItem", "checked": checked, "children": children })
+}
+
+/// The children of a definition list item. The term leads the first
+/// paragraph of the definition, as only that paragraph sits beside the
+/// bullet.
+pub fn definition(term: Vec<Value>, mut body: Vec<Value>) -> Vec<Value> {
+    if term.is_empty() {
+        return body;
+    }
+    let mut inline = term;
+    match body.first_mut() {
+        Some(first) if first["type"] == "paragraph" => {
+            inline.push(text(" — "));
+            if let Some(children) = first["children"].as_array_mut() {
+                inline.append(children);
+            }
+            *first = paragraph(inline);
+            body
+        }
+        _ => {
+            body.insert(0, paragraph(inline));
+            body
+        }
+    }
+}
+
+/// A list item of a format that marks nesting explicitly, like AsciiDoc's
+/// `**` or an indentation depth.
+pub struct ListEntry {
+    pub level: usize,
+    pub ordered: bool,
+    pub checked: Option<bool>,
+    pub children: Vec<Value>,
+}
+
+/// Builds a list from a flat run of entries, nesting each entry that is
+/// deeper than the one before it inside that one.
+pub fn nested_list(entries: Vec<ListEntry>) -> Value {
+    let mut entries = entries.into_iter().peekable();
+    let level = entries.peek().map_or(0, |entry| entry.level);
+    build_list(&mut entries, level)
+}
+
+fn build_list(
+    entries: &mut std::iter::Peekable<std::vec::IntoIter<ListEntry>>,
+    level: usize,
+) -> Value {
+    let ordered = entries.peek().is_some_and(|entry| entry.ordered);
+    let mut items: Vec<Value> = Vec::new();
+
+    while let Some(entry_level) = entries.peek().map(|entry| entry.level) {
+        if entry_level < level {
+            break;
+        }
+        if entry_level > level && !items.is_empty() {
+            let sublist = build_list(entries, entry_level);
+            if let Some(children) = items
+                .last_mut()
+                .and_then(|item| item["children"].as_array_mut())
+            {
+                children.push(sublist);
+            }
+            continue;
+        }
+        if let Some(entry) = entries.next() {
+            items.push(list_item(entry.checked, entry.children));
+        }
+    }
+
+    list(ordered, items)
+}
+
+/// A table whose first row is the header; cells hold plain text.
+pub fn table(rows: Vec<Vec<String>>) -> Value {
+    let rows: Vec<Value> = rows
+        .into_iter()
+        .map(|cells| {
+            let cells: Vec<Value> = cells
+                .iter()
+                .map(|cell| json!({ "type": "tableCell", "children": [text(cell)] }))
+                .collect();
+            json!({ "type": "tableRow", "children": cells })
+        })
+        .collect();
+    json!({ "type": "table", "children": rows })
+}
+
+/// A GitHub-style admonition, which the renderer recognises from a
+/// blockquote starting with `[!KIND]`.
+pub fn admonition(kind: &str, content: &str) -> Value {
+    blockquote(vec![paragraph(vec![text(&format!(
+        "[!{}] {}",
+        kind.to_uppercase(),
+        content
+    ))])])
+}
+
+/// The plain text of a node and everything inside it.
+pub fn node_text(node: &Value) -> String {
+    if is_separator(node) {
+        return " ".to_string();
+    }
+    match node["value"].as_str() {
+        Some(value) => value.to_string(),
+        None => node["children"]
+            .as_array()
+            .map(|children| children.iter().map(node_text).collect())
+            .unwrap_or_default(),
+    }
+}
+
+pub fn thematic_break() -> Value {
+    json!({ "type": "thematicBreak" })
+}
@@ -1,12 +1,27 @@
 pub mod ast;
+pub mod mdast;
 pub mod shared;
 
 mod detect_language;
+mod encoding;
+mod file_type;
 mod highlighter;
+mod image_cache;
 mod images;
 mod theme;
 
 // pub use emoji::parse_emoji;
 pub use detect_language::detect_language;
-pub use highlighter::highlight_code;
-pub use images::download_image;
+pub use encoding::{
+    decode_stream, decode_text, detect_encoding, detect_file_encoding, file_encoding,
+    read_text_file,
+};
+pub use file_type::{format_size, is_binary_file, read_head, SNIFF_LEN};
+pub use highlighter::{highlight_code, highlight_code_with_background};
+pub use image_cache::{
+    clear_image_cache, client_with_limits, download_image, prefetch_images, read_body,
+    DownloadLimits,
+};
+pub use images::{
+    is_svg, looks_like_svg, orient_image, rasterize_svg, read_exif, CELL_HEIGHT_PX, CELL_WIDTH_PX,
+};
@@ -1,7 +1,25 @@
+use crate::animation::print_animation_summary;
 use crate::app;
+use crate::archive::{is_archive, render_archive};
+use crate::asciidoc::render_asciidoc;
+use crate::compression::inner_path;
 use crate::config::get_config;
-use crate::render::{render_code_file, render_image_file, render_markdown};
-use crate::utils::detect_language;
+use crate::diff::{looks_like_diff, render_diff};
+use crate::git::line_changes;
+use crate::hex_dump::render_hex_dump;
+use crate::html::render_html_document;
+use crate::image_info::print_image_info;
+use crate::log::{render_log, stream_log_file};
+use crate::man::{is_man_page, render_man};
+use crate::notebook::render_notebook;
+use crate::org::render_org;
+use crate::render::{
+    render_code_file, render_image_file, render_markdown, set_document_path, stream_code,
+};
+use crate::rst::render_rst;
+use crate::sqlite::{is_sqlite_file, render_sqlite};
+use crate::stream::{line_number_width, open_text_stream};
+use crate::utils::{detect_language, file_encoding, is_binary_file};
 use base64::{engine::general_purpose, Engine as _};
 use devicons::{icon_for_file, File, Theme};
 use std::collections::HashMap;
@@ -22,6 +40,17 @@ impl ViewerManager {
         viewer_manager.register_viewer("markdown", Box::new(MarkdownViewer));
         viewer_manager.register_viewer("code", Box::new(CodeViewer));
         viewer_manager.register_viewer("image", Box::new(ImageViewer));
+        viewer_manager.register_viewer("hex", Box::new(HexViewer));
+        viewer_manager.register_viewer("notebook", Box::new(NotebookViewer));
+        viewer_manager.register_viewer("diff", Box::new(DiffViewer));
+        viewer_manager.register_viewer("archive", Box::new(ArchiveViewer));
+        viewer_manager.register_viewer("sqlite", Box::new(SqliteViewer));
+        viewer_manager.register_viewer("log", Box::new(LogViewer));
+        viewer_manager.register_viewer("html", Box::new(HtmlViewer));
+        viewer_manager.register_viewer("man", Box::new(ManViewer));
+        viewer_manager.register_viewer("rst", Box::new(RstViewer));
+        viewer_manager.register_viewer("asciidoc", Box::new(AsciidocViewer));
+        viewer_manager.register_viewer("org", Box::new(OrgViewer));
         viewer_manager
     }
 
@@ -50,9 +79,14 @@ impl ViewerManager {
                 stdout.set_color(ColorSpec::new().set_fg(Some(Color::Blue)).set_bold(true))?;
                 writeln!(stdout)?;
                 stdout.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
-                writeln!(stdout, "{}  {}", icon.icon, file_name)?;
+                write!(stdout, "{}  {}", icon.icon, file_name)?;
+                if let Some(encoding) = file_encoding(Path::new(path)) {
+                    stdout.set_color(ColorSpec::new().set_dimmed(true))?;
+                    write!(stdout, "  {}", encoding.name())?;
+                }
                 stdout.reset()?;
                 writeln!(stdout)?;
+                writeln!(stdout)?;
             }
         }
 
@@ -77,7 +111,8 @@ pub trait Viewer {
 struct MarkdownViewer;
 
 impl Viewer for MarkdownViewer {
-    fn visualize(&self, content: &str, _file_path: Option<&str>) -> io::Result<()> {
+    fn visualize(&self, content: &str, file_path: Option<&str>) -> io::Result<()> {
+        set_document_path(file_path);
         let json = app::parse_and_process_markdown(content)?;
         render_markdown(&json)
     }
@@ -87,10 +122,16 @@ struct CodeViewer;
 
 impl Viewer for CodeViewer {
     fn visualize(&self, content: &str, file_path: Option<&str>) -> io::Result<()> {
-        let language = file_path
-            .map(|path| detect_language(path))
-            .unwrap_or_else(|| "txt".to_string());
-        render_code_file(content, &language)
+        match file_path {
+            // Files are highlighted as they are read, whatever their size
+            Some(path) => stream_code(
+                open_text_stream(Path::new(path))?,
+                &detect_language(&inner_path(Path::new(path)).to_string_lossy()),
+                line_number_width(Path::new(path))?,
+                line_changes(Path::new(path)),
+            ),
+            None => render_code_file(content, "txt"),
+        }
     }
 }
 
@@ -99,7 +140,8 @@ struct ImageViewer;
 impl Viewer for ImageViewer {
     fn visualize(&self, content: &str, file_path: Option<&str>) -> io::Result<()> {
         if let Some(path) = file_path {
-            render_image_file(path)
+            render_image_file(path)?;
+            print_image_details(Path::new(path))
         } else if content.starts_with("data:image") {
             // Handle base64 encoded image
             let parts: Vec<&str> = content.split(',').collect();
@@ -114,7 +156,8 @@ impl Viewer for ImageViewer {
                 temp_file.write_all(&decoded)?;
                 let temp_path = temp_file.into_temp_path();
 
-                render_image_file(temp_path.to_str().unwrap())
+                render_image_file(temp_path.to_str().unwrap())?;
+                print_image_details(&temp_path)
             } else {
                 Err(io::Error::new(
                     io::ErrorKind::InvalidData,
@@ -130,7 +173,139 @@ impl Viewer for ImageViewer {
     }
 }
 
+fn print_image_details(path: &Path) -> io::Result<()> {
+    print_animation_summary(path)?;
+    if get_config().image_info {
+        print_image_info(path)?;
+    }
+    Ok(())
+}
+
+struct HexViewer;
+
+impl Viewer for HexViewer {
+    fn visualize(&self, content: &str, file_path: Option<&str>) -> io::Result<()> {
+        match file_path {
+            Some(path) => render_hex_dump(std::fs::File::open(path)?),
+            None => render_hex_dump(io::Cursor::new(content.as_bytes())),
+        }
+    }
+}
+
+struct NotebookViewer;
+
+impl Viewer for NotebookViewer {
+    fn visualize(&self, content: &str, file_path: Option<&str>) -> io::Result<()> {
+        set_document_path(file_path);
+        render_notebook(content)
+    }
+}
+
+struct DiffViewer;
+
+impl Viewer for DiffViewer {
+    fn visualize(&self, content: &str, _file_path: Option<&str>) -> io::Result<()> {
+        render_diff(content)
+    }
+}
+
+struct ArchiveViewer;
+
+impl Viewer for ArchiveViewer {
+    fn visualize(&self, _content: &str, file_path: Option<&str>) -> io::Result<()> {
+        match file_path {
+            Some(path) => render_archive(Path::new(path)),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Archives can only be listed from a file",
+            )),
+        }
+    }
+}
+
+struct SqliteViewer;
+
+impl Viewer for SqliteViewer {
+    fn visualize(&self, _content: &str, file_path: Option<&str>) -> io::Result<()> {
+        match file_path {
+            Some(path) => render_sqlite(Path::new(path)),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "SQLite databases can only be read from a file",
+            )),
+        }
+    }
+}
+
+struct LogViewer;
+
+impl Viewer for LogViewer {
+    fn visualize(&self, content: &str, file_path: Option<&str>) -> io::Result<()> {
+        match file_path {
+            Some(path) => stream_log_file(Path::new(path)),
+            None => render_log(content),
+        }
+    }
+}
+
+struct HtmlViewer;
+
+impl Viewer for HtmlViewer {
+    fn visualize(&self, content: &str, file_path: Option<&str>) -> io::Result<()> {
+        set_document_path(file_path);
+        render_html_document(content)
+    }
+}
+
+struct ManViewer;
+
+impl Viewer for ManViewer {
+    fn visualize(&self, content: &str, _file_path: Option<&str>) -> io::Result<()> {
+        render_man(content)
+    }
+}
+
+struct RstViewer;
+
+impl Viewer for RstViewer {
+    fn visualize(&self, content: &str, file_path: Option<&str>) -> io::Result<()> {
+        set_document_path(file_path);
+        render_rst(content)
+    }
+}
+
+struct AsciidocViewer;
+
+impl Viewer for AsciidocViewer {
+    fn visualize(&self, content: &str, file_path: Option<&str>) -> io::Result<()> {
+        set_document_path(file_path);
+        render_asciidoc(content)
+    }
+}
+
+struct OrgViewer;
+
+impl Viewer for OrgViewer {
+    fn visualize(&self, content: &str, file_path: Option<&str>) -> io::Result<()> {
+        set_document_path(file_path);
+        render_org(content)
+    }
+}
+
+/// Viewers explicitly requested with `--viewer`, if any.
+pub fn forced_viewer() -> Option<Vec<String>> {
+    get_config().viewer.as_ref().map(|names| {
+        names
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .collect()
+    })
+}
+
 pub fn determine_viewer(file_path: &Path) -> Vec<String> {
+    if let Some(viewer) = forced_viewer() {
+        return viewer;
+    }
     let extension = file_path
         .extension()
         .and_then(std::ffi::OsStr::to_str)
@@ -138,7 +313,44 @@ pub fn determine_viewer(file_path: &Path) -> Vec<String> {
         .to_lowercase();
     match extension.as_str() {
         "md" => vec!["markdown".to_string()],
-        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "webp" => vec!["image".to_string()],
+        "ipynb" => vec!["notebook".to_string()],
+        "diff" | "patch" => vec!["diff".to_string()],
+        "log" => vec!["log".to_string()],
+        "html" | "htm" | "xhtml" => vec!["html".to_string()],
+        "rst" | "rest" => vec!["rst".to_string()],
+        "adoc" | "asciidoc" => vec!["asciidoc".to_string()],
+        "org" => vec!["org".to_string()],
+        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "webp" | "svg" => vec!["image".to_string()],
+        _ if is_sqlite_file(file_path) => vec!["sqlite".to_string()],
+        _ if is_archive(file_path) => vec!["archive".to_string()],
+        _ if is_binary_file(file_path) => vec!["hex".to_string()],
+        _ if is_man_page(file_path) => vec!["man".to_string()],
         _ => vec!["code".to_string()],
     }
 }
+
+/// Viewers that read the file themselves rather than taking its text content.
+pub fn reads_raw_file(viewer_names: &[String]) -> bool {
+    viewer_names
+        .iter()
+        .any(|name| matches!(name.as_str(), "hex" | "archive" | "sqlite"))
+}
+
+/// Viewers that read text files line by line as they show them, so a file
+/// never has to fit in memory.
+pub fn streams_file(viewer_names: &[String]) -> bool {
+    viewer_names
+        .iter()
+        .all(|name| matches!(name.as_str(), "code" | "log"))
+}
+
+/// Picks a viewer for piped input, where there is no file name to go by.
+pub fn determine_stdin_viewer(content: &str) -> Vec<String> {
+    if let Some(viewer) = forced_viewer() {
+        viewer
+    } else if looks_like_diff(content) {
+        vec!["diff".to_string()]
+    } else {
+        vec!["markdown".to_string()]
+    }
+}
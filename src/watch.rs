@@ -0,0 +1,280 @@
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, SetAttribute};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+use time::{OffsetDateTime, UtcOffset};
+use url::Url;
+
+use crate::app;
+use crate::graphics::GraphicsProtocol;
+use crate::utils::ast;
+
+/// How often watched files are checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A watched file's modification time and size, or `None` while it is
+/// missing, as it briefly is when an editor replaces it on save.
+type Snapshot = Vec<Option<(SystemTime, u64)>>;
+
+/// The rendered output shown in the alternate screen.
+struct View {
+    lines: Vec<String>,
+    /// Index of the first line on screen, kept across re-renders.
+    offset: usize,
+    refreshed: String,
+    error: Option<String>,
+}
+
+/// Puts the terminal back the way it was, even when watching fails.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(
+            io::stdout(),
+            terminal::EnterAlternateScreen,
+            terminal::DisableLineWrap,
+            cursor::Hide
+        )?;
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(
+            io::stdout(),
+            cursor::Show,
+            terminal::EnableLineWrap,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Shows the files and renders them again whenever they, or the local images
+/// a Markdown file references, change. The output can be scrolled, and the
+/// position is kept across refreshes.
+pub fn watch_files(paths: &[PathBuf]) -> io::Result<()> {
+    if let Some(path) = paths.iter().find(|path| !path.exists()) {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "--watch needs local files, {} does not exist",
+                path.display()
+            ),
+        ));
+    }
+
+    // The offset can only be read safely before other threads start
+    let utc_offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+    let _guard = TerminalGuard::enter()?;
+
+    let mut view = View {
+        lines: Vec::new(),
+        offset: 0,
+        refreshed: String::new(),
+        error: None,
+    };
+    let mut watched = watched_files(paths);
+    let mut snapshot = take_snapshot(&watched);
+    refresh(&mut view, utc_offset);
+    draw(&view, paths)?;
+
+    loop {
+        if event::poll(WATCH_INTERVAL)? {
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => {
+                    if !scroll(&mut view, key)? {
+                        return Ok(());
+                    }
+                }
+                // The layout depends on the terminal width
+                Event::Resize(..) => refresh(&mut view, utc_offset),
+                _ => continue,
+            }
+            draw(&view, paths)?;
+            continue;
+        }
+
+        let current = take_snapshot(&watched);
+        if current != snapshot {
+            // Images may have been added to or removed from the document
+            watched = watched_files(paths);
+            snapshot = take_snapshot(&watched);
+            refresh(&mut view, utc_offset);
+            draw(&view, paths)?;
+        }
+    }
+}
+
+/// Handles a key press, returning false when it asks to quit.
+fn scroll(view: &mut View, key: KeyEvent) -> io::Result<bool> {
+    let page = usize::from(terminal::size()?.1.saturating_sub(1).max(1));
+    let max_offset = view.lines.len().saturating_sub(page);
+    view.offset = view.offset.min(max_offset);
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(false),
+        KeyCode::Down | KeyCode::Char('j') | KeyCode::Enter => view.offset += 1,
+        KeyCode::Up | KeyCode::Char('k') => view.offset = view.offset.saturating_sub(1),
+        KeyCode::PageDown | KeyCode::Char(' ') | KeyCode::Char('f') => view.offset += page,
+        KeyCode::PageUp | KeyCode::Char('b') => view.offset = view.offset.saturating_sub(page),
+        KeyCode::Home | KeyCode::Char('g') => view.offset = 0,
+        KeyCode::End | KeyCode::Char('G') => view.offset = max_offset,
+        _ => {}
+    }
+    view.offset = view.offset.min(max_offset);
+    Ok(true)
+}
+
+/// Renders the files again in a child process and captures its output. When
+/// that fails, as it can halfway through a save, the last output stays up.
+fn refresh(view: &mut View, utc_offset: UtcOffset) {
+    match render_output() {
+        Ok(output) => {
+            view.lines = output.lines().map(str::to_string).collect();
+            view.error = None;
+        }
+        Err(e) => view.error = Some(e.to_string()),
+    }
+    let now = OffsetDateTime::now_utc().to_offset(utc_offset);
+    view.refreshed = format!("{:02}:{:02}:{:02}", now.hour(), now.minute(), now.second());
+}
+
+fn render_output() -> io::Result<String> {
+    let args: Vec<String> = env::args()
+        .skip(1)
+        .filter(|arg| {
+            !["--watch", "--follow"]
+                .iter()
+                .any(|option| arg == option || arg.starts_with(&format!("{}=", option)))
+        })
+        .collect();
+    let mut command = Command::new(env::current_exe()?);
+    // Turned off explicitly, as the config file may turn them on again and
+    // the child would then never exit
+    command
+        .args(&args)
+        .args(["--force-render", "--watch=false", "--follow=false"]);
+    // Graphics protocols draw over the text, so images are drawn as coloured
+    // blocks that scroll with it
+    if !matches!(
+        GraphicsProtocol::configured(),
+        GraphicsProtocol::Ascii | GraphicsProtocol::None
+    ) {
+        command.arg("--graphics-protocol=halfblocks");
+    }
+
+    let output = command.output()?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(io::Error::other(if message.is_empty() {
+            format!("rendering failed ({})", output.status)
+        } else {
+            message
+        }));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn draw(view: &View, paths: &[PathBuf]) -> io::Result<()> {
+    let (columns, rows) = terminal::size()?;
+    let body_rows = usize::from(rows.saturating_sub(1));
+    let offset = view.offset.min(view.lines.len().saturating_sub(body_rows));
+
+    let mut stdout = io::stdout();
+    queue!(stdout, terminal::Clear(ClearType::All))?;
+    for (row, line) in view.lines.iter().skip(offset).take(body_rows).enumerate() {
+        queue!(stdout, cursor::MoveTo(0, row as u16))?;
+        write!(stdout, "{}\x1b[0m", line)?;
+    }
+
+    let names: Vec<String> = paths
+        .iter()
+        .map(|path| {
+            path.file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    let last_line = (offset + body_rows).min(view.lines.len());
+    let mut status = format!(
+        " {} · refreshed {} · lines {}-{} of {} · q to quit",
+        names.join(", "),
+        view.refreshed,
+        (offset + 1).min(last_line),
+        last_line,
+        view.lines.len()
+    );
+    if let Some(error) = &view.error {
+        status = format!(" {} · {}", error.lines().next().unwrap_or_default(), status);
+    }
+    let status: String = status.chars().take(usize::from(columns)).collect();
+
+    queue!(
+        stdout,
+        cursor::MoveTo(0, rows.saturating_sub(1)),
+        SetAttribute(Attribute::Reverse)
+    )?;
+    write!(stdout, "{:<width$}", status, width = usize::from(columns))?;
+    queue!(stdout, SetAttribute(Attribute::Reset))?;
+    stdout.flush()
+}
+
+/// The files to watch: the given ones and the local images their Markdown
+/// refers to.
+fn watched_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut watched = paths.to_vec();
+    for path in paths {
+        if path.extension().is_some_and(|extension| extension == "md") {
+            watched.extend(referenced_images(path));
+        }
+    }
+    watched
+}
+
+fn referenced_images(path: &Path) -> Vec<PathBuf> {
+    let Ok(content) = app::read_content(Some(path.to_string_lossy().into_owned())) else {
+        return Vec::new();
+    };
+    let Ok(json) = app::parse_and_process_markdown(&content) else {
+        return Vec::new();
+    };
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let Some(base) = std::path::absolute(dir)
+        .ok()
+        .and_then(|dir| Url::from_directory_path(dir).ok())
+    else {
+        return Vec::new();
+    };
+
+    let mut urls = Vec::new();
+    ast::collect_image_urls(&json, &mut urls);
+    urls.iter()
+        .filter_map(|url| base.join(url).ok())
+        .filter(|url| url.scheme() == "file")
+        .filter_map(|url| url.to_file_path().ok())
+        .collect()
+}
+
+fn take_snapshot(paths: &[PathBuf]) -> Snapshot {
+    paths
+        .iter()
+        .map(|path| {
+            let metadata = fs::metadata(path).ok()?;
+            Some((metadata.modified().ok()?, metadata.len()))
+        })
+        .collect()
+}